@@ -1,3 +0,0 @@
-fn main() {
-    println!("cargo:rustc-cfg=fuzz");
-}
@@ -1,12 +1,8 @@
-#[macro_use]
-extern crate cfg_if;
 extern crate futures as _futures;
+extern crate syncbox;
 extern crate syncbox_fuzz;
 
-#[path = "../../../src/futures/atomic_task.rs"]
-mod atomic_task;
-
-use atomic_task::AtomicTask;
+use syncbox::futures::AtomicTask;
 
 use syncbox_fuzz::{
     fuzz_future,
@@ -28,8 +24,8 @@ struct Chan {
 
 fn main() {
     let mut fuzz = syncbox_fuzz::fuzz::Builder::new();
-    fuzz.runtime = syncbox_fuzz::fuzz::Runtime::Fringe;
     fuzz.checkpoint_interval = 10;
+    fuzz.apply_env();
 
     fuzz.fuzz_future(|| {
         let chan = Arc::new(Chan {
@@ -1,11 +1,7 @@
-#[macro_use]
-extern crate cfg_if;
+extern crate syncbox;
 extern crate syncbox_fuzz;
 
-#[path = "../../../src/parker.rs"]
-mod parker;
-
-use parker::*;
+use syncbox::Parker;
 
 use syncbox_fuzz::{
     fuzz,
@@ -1,16 +1,9 @@
 #[macro_use]
-extern crate cfg_if;
-extern crate crossbeam_utils;
-#[macro_use]
 extern crate futures as _futures;
-#[macro_use]
+extern crate syncbox;
 extern crate syncbox_fuzz;
 
-#[allow(warnings)]
-#[path = "../../../src/futures/semaphore.rs"]
-mod semaphore;
-
-use semaphore::*;
+use syncbox::futures::{Semaphore, SemaphoreWaiter as Waiter};
 
 use syncbox_fuzz::{
     fuzz,
@@ -59,10 +52,8 @@ impl Future for Actor {
 
 fn main() {
     let mut fuzz = fuzz::Builder::new();
-    // fuzz.log = true;
-    // fuzz.checkpoint_file("syncbox-fuzz.txt");
     fuzz.checkpoint_interval = 1_000;
-    fuzz.runtime = fuzz::Runtime::Fringe;
+    fuzz.apply_env();
 
     fuzz.fuzz_future(|| {
         let shared = Arc::new(Shared {
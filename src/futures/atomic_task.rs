@@ -1,16 +1,11 @@
+use sync::CausalCell;
+use sync::atomic::AtomicUsize;
+
 cfg_if! {
-    if #[cfg(fuzz)] {
-        use syncbox_fuzz::{
-            futures::task::{self, Task},
-            sync::{
-                CausalCell,
-                atomic::AtomicUsize,
-            },
-        };
+    if #[cfg(feature = "fuzz")] {
+        use syncbox_fuzz::futures::task::{self, Task};
     } else {
-        use CausalCell;
         use _futures::task::{self, Task};
-        use std::sync::atomic::AtomicUsize;
     }
 }
 
@@ -149,9 +144,13 @@ const NOTIFYING: usize = 0b10;
 impl AtomicTask {
     /// Create an `AtomicTask` initialized with the given `Task`
     pub fn new() -> AtomicTask {
-        // Make sure that task is Sync
-        trait AssertSync: Sync {}
-        impl AssertSync for Task {}
+        // Make sure that task is Sync. Model tasks are confined to the model
+        // threads of an execution.
+        #[cfg(not(feature = "fuzz"))]
+        {
+            trait AssertSync: Sync {}
+            impl AssertSync for Task {}
+        }
 
         AtomicTask {
             state: AtomicUsize::new(WAITING),
@@ -1,18 +1,10 @@
+use sync::{AtomicTask, CausalCell, yield_now};
+use sync::atomic::{AtomicUsize, AtomicPtr};
+
 cfg_if! {
-    if #[cfg(fuzz)] {
-        use syncbox_fuzz::{
-            futures::AtomicTask,
-            sync::{
-                CausalCell,
-                atomic::{AtomicUsize, AtomicPtr},
-            },
-            yield_now,
-        };
+    if #[cfg(feature = "fuzz")] {
+        use syncbox_fuzz::debug;
     } else {
-        use CausalCell;
-        use _futures::task::AtomicTask;
-        use std::sync::atomic::{AtomicUsize, AtomicPtr};
-
         macro_rules! debug {
             ($($t:tt)*) => {};
         }
@@ -2,11 +2,16 @@
 extern crate cfg_if;
 extern crate crossbeam_utils;
 
+#[cfg(feature = "fuzz")]
+extern crate syncbox_fuzz;
+
+#[cfg(not(feature = "fuzz"))]
 mod causal;
 mod parker;
+pub mod sync;
 
-pub use self::causal::CausalCell;
 pub use self::parker::Parker;
+pub use self::sync::CausalCell;
 
 cfg_if! {
     if #[cfg(feature = "futures")] {
@@ -14,6 +19,3 @@ cfg_if! {
         pub mod futures;
     }
 }
-
-#[cfg(feature = "fuzz")]
-extern crate syncbox_fuzz;
@@ -1,17 +1,5 @@
-cfg_if! {
-    if #[cfg(fuzz)] {
-        use syncbox_fuzz::{
-            sync::{
-                atomic::AtomicUsize,
-                Condvar,
-                Mutex,
-            },
-        };
-    } else {
-        use std::sync::{Condvar, Mutex};
-        use std::sync::atomic::AtomicUsize;
-    }
-}
+use sync::{Condvar, Mutex};
+use sync::atomic::AtomicUsize;
 
 use std::fmt;
 use std::sync::atomic::Ordering::SeqCst;
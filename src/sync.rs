@@ -0,0 +1,50 @@
+//! Synchronization primitives that can be model checked.
+//!
+//! By default, these are the `std` primitives. With the `fuzz` feature, they
+//! are the `syncbox_fuzz` models with the same APIs, and must only be used
+//! from within `syncbox_fuzz::fuzz`. Code written against this module is
+//! model checked by enabling the feature, without any `cfg` of its own.
+
+cfg_if! {
+    if #[cfg(feature = "fuzz")] {
+        pub use syncbox_fuzz::sync::{
+            CausalCell,
+            Condvar,
+            Mutex,
+            MutexGuard,
+            WaitTimeoutResult,
+        };
+
+        pub use syncbox_fuzz::{thread, yield_now};
+
+        /// Atomic types
+        pub mod atomic {
+            pub use syncbox_fuzz::sync::atomic::{AtomicPtr, AtomicUsize};
+            pub use std::sync::atomic::Ordering;
+        }
+    } else {
+        pub use causal::CausalCell;
+
+        pub use std::sync::{
+            Condvar,
+            Mutex,
+            MutexGuard,
+            WaitTimeoutResult,
+        };
+
+        pub use std::thread::{self, yield_now};
+
+        /// Atomic types
+        pub mod atomic {
+            pub use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+        }
+    }
+}
+
+cfg_if! {
+    if #[cfg(all(feature = "futures", feature = "fuzz"))] {
+        pub use syncbox_fuzz::futures::AtomicTask;
+    } else if #[cfg(feature = "futures")] {
+        pub use _futures::task::AtomicTask;
+    }
+}
@@ -0,0 +1,119 @@
+//! Attribute macro for writing model-checked tests.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use syn::{AttributeArgs, ItemFn, Lit, Meta, NestedMeta, ReturnType};
+
+/// Turns a function into a `#[test]` that is run by the model checker.
+///
+/// The following options map to the `fuzz::Builder` fields of the same name:
+///
+/// * `max_threads = 2`
+/// * `runtime = "thread"`: one of `thread`, `generator`, `fringe` or `ucontext`
+/// * `max_preemptions = 2`
+/// * `checkpoint_file = "path"`
+///
+/// Functions returning a future are checked with `fuzz_future`.
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item = parse_macro_input!(item as ItemFn);
+
+    match expand(args, item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(args: AttributeArgs, item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    if !item.decl.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.decl.inputs,
+            "model-checked tests cannot take arguments"));
+    }
+
+    let mut options = vec![];
+
+    for arg in args {
+        options.push(option(arg)?);
+    }
+
+    let ItemFn { attrs, vis, ident, decl, block, .. } = item;
+
+    let output = &decl.output;
+
+    let run = match *output {
+        ReturnType::Default => quote!(fuzz),
+        ReturnType::Type(..) => quote!(fuzz_future),
+    };
+
+    Ok(quote! {
+        #[test]
+        #(#attrs)*
+        #vis fn #ident() {
+            fn body() #output #block
+
+            let mut builder = ::syncbox_fuzz::fuzz::Builder::new();
+            #(#options)*
+            builder.#run(body);
+        }
+    })
+}
+
+/// Translate a `name = value` argument into a statement configuring `builder`
+fn option(arg: NestedMeta) -> syn::Result<proc_macro2::TokenStream> {
+    let (name, lit) = match arg {
+        NestedMeta::Meta(Meta::NameValue(meta)) => (meta.ident, meta.lit),
+        arg => {
+            return Err(syn::Error::new_spanned(arg, "expected `name = value`"));
+        }
+    };
+
+    let stmt = match (&name.to_string()[..], &lit) {
+        ("max_threads", &Lit::Int(ref n)) => {
+            let n = n.value() as usize;
+            quote!(builder.max_threads = #n;)
+        }
+        ("max_preemptions", &Lit::Int(ref n)) => {
+            let n = n.value() as usize;
+            quote!(builder.max_preemptions = Some(#n);)
+        }
+        ("checkpoint_file", &Lit::Str(ref file)) => {
+            quote!(builder.checkpoint_file(#file);)
+        }
+        ("runtime", &Lit::Str(ref runtime)) => {
+            let variant = match &runtime.value()[..] {
+                "thread" => "Thread",
+                "generator" => "Generator",
+                "fringe" => "Fringe",
+                "ucontext" => "Ucontext",
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        runtime,
+                        "expected one of `thread`, `generator`, `fringe` or `ucontext`"));
+                }
+            };
+
+            let variant = syn::Ident::new(variant, Span::call_site());
+            quote!(builder.runtime = ::syncbox_fuzz::fuzz::Runtime::#variant;)
+        }
+        ("max_threads", _) | ("max_preemptions", _) => {
+            return Err(syn::Error::new_spanned(lit, "expected an integer"));
+        }
+        ("checkpoint_file", _) | ("runtime", _) => {
+            return Err(syn::Error::new_spanned(lit, "expected a string"));
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(name, "unknown option"));
+        }
+    };
+
+    Ok(stmt)
+}
@@ -0,0 +1,57 @@
+//! Compares the cost of an execution across runtimes.
+//!
+//! Run with `cargo run --release --example runtimes`.
+
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::{Builder, Runtime};
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Two threads incrementing a counter while the main thread reads it
+fn increments() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+
+        thread::spawn(move || {
+            for _ in 0..4 {
+                num.fetch_add(1, SeqCst);
+            }
+        })
+    }).collect();
+
+    num.load(SeqCst);
+
+    for th in ths {
+        th.join().unwrap();
+    }
+}
+
+fn bench(name: &str, runtime: Runtime) {
+    let mut builder = Builder::new();
+    builder.runtime = runtime;
+    builder.checkpoint_interval = usize::max_value();
+
+    let report = builder.fuzz(increments);
+    let nanos = report.elapsed.as_secs() * 1_000_000_000 +
+        report.elapsed.subsec_nanos() as u64;
+
+    println!(
+        "{:<10} {:>6} executions {:>8.1}us/exec",
+        name,
+        report.executions,
+        nanos as f64 / report.executions as f64 / 1_000.0);
+}
+
+fn main() {
+    bench("generator", Runtime::Generator);
+    bench("thread", Runtime::Thread);
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    bench("ucontext", Runtime::Ucontext);
+}
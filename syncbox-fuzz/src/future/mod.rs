@@ -0,0 +1,23 @@
+//! Model checking support for `std::future`.
+
+use rt;
+
+use std::future::Future;
+
+/// Drive a future to completion on the current model thread.
+///
+/// Waking the future's `Waker` notifies the current thread.
+pub fn block_on<F>(f: F) -> F::Output
+where
+    F: Future,
+{
+    rt::block_on(f)
+}
+
+/// Spawn a future onto a new model thread.
+pub fn spawn<F>(f: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    rt::spawn(move || rt::block_on(f));
+}
@@ -0,0 +1,111 @@
+use rt;
+use _futures::Future;
+
+use std::fmt;
+
+/// A single threaded executor.
+///
+/// All tasks are polled on the model thread that calls `run`. Each task tracks
+/// its own notification, so notifications sent to different tasks on the same
+/// thread are not coalesced. When more than one task is ready, the task to
+/// poll next is an explored branch.
+pub struct Executor {
+    tasks: Vec<Option<Box<Future<Item = (), Error = ()>>>>,
+}
+
+impl Executor {
+    pub fn new() -> Executor {
+        Executor {
+            tasks: vec![],
+        }
+    }
+
+    pub fn spawn<F>(&mut self, f: F)
+    where
+        F: Future<Item = (), Error = ()> + 'static,
+    {
+        self.tasks.push(Some(Box::new(f)));
+    }
+
+    /// Run all spawned tasks to completion
+    pub fn run(&mut self) {
+        let num_tasks = self.tasks.len();
+
+        rt::execution(|execution| {
+            let thread = execution.threads.active_mut();
+
+            assert!(thread.tasks.is_empty(), "executor is already running");
+
+            // All tasks start out notified
+            thread.tasks = vec![Some(true); num_tasks];
+        });
+
+        let mut remaining = num_tasks;
+
+        while remaining > 0 {
+            let next = rt::execution(|execution| {
+                let ready: Vec<_> = {
+                    let tasks = &execution.threads.active().tasks;
+
+                    (0..num_tasks)
+                        .filter(|&i| tasks[i] == Some(true))
+                        .collect()
+                };
+
+                if ready.is_empty() {
+                    return None;
+                }
+
+                let next = execution.path.branch_write(rt::path::Kind::Poll, ready.into_iter());
+
+                let thread = execution.threads.active_mut();
+                thread.tasks[next] = Some(false);
+                thread.active_task = Some(next);
+
+                Some(next)
+            });
+
+            let next = match next {
+                Some(next) => next,
+                None => {
+                    rt::park_future();
+                    continue;
+                }
+            };
+
+            let res = self.tasks[next].as_mut().unwrap().poll().unwrap();
+
+            rt::execution(|execution| {
+                let thread = execution.threads.active_mut();
+                thread.active_task = None;
+
+                if res.is_ready() {
+                    thread.tasks[next] = None;
+                }
+            });
+
+            if res.is_ready() {
+                self.tasks[next] = None;
+                remaining -= 1;
+            }
+        }
+
+        rt::execution(|execution| {
+            execution.threads.active_mut().tasks.clear();
+        });
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Executor::new()
+    }
+}
+
+impl fmt::Debug for Executor {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Executor")
+            .field("tasks", &self.tasks.len())
+            .finish()
+    }
+}
@@ -1,6 +1,8 @@
 mod atomic_task;
+mod executor;
 
 pub use self::atomic_task::AtomicTask;
+pub use self::executor::Executor;
 
 use rt;
 use _futures::Future;
@@ -18,17 +20,43 @@ pub mod task {
     #[derive(Debug)]
     pub struct Task {
         thread: rt::thread::Id,
+        task: Option<usize>,
     }
 
     pub fn current() -> Task {
-        Task {
-            thread: rt::thread::Id::current(),
-        }
+        rt::execution(|execution| {
+            execution.threads.active_mut().task_refs += 1;
+
+            Task {
+                thread: execution.threads.active_id(),
+                task: execution.threads.active().active_task,
+            }
+        })
     }
 
     impl Task {
         pub fn notify(&self) {
-            self.thread.future_notify();
+            match self.task {
+                Some(task) => self.thread.task_notify(task),
+                None => self.thread.future_notify(),
+            }
+        }
+    }
+
+    impl Clone for Task {
+        fn clone(&self) -> Task {
+            self.thread.task_ref();
+
+            Task {
+                thread: self.thread,
+                task: self.task,
+            }
+        }
+    }
+
+    impl Drop for Task {
+        fn drop(&mut self) {
+            self.thread.task_unref();
         }
     }
 }
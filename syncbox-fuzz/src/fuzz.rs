@@ -1,25 +1,55 @@
-use rt::{self, Execution, Scheduler};
+use rt::{self, Execution, Path, Scheduler};
+use rt::graph::Graph;
+use rt::path::{Kind, Shard};
+use rt::timeline::Timeline;
+use scheduler;
+use shrink;
+use workers;
 
+use serde::Serialize;
 use serde_json;
+use std::any::Any;
+use std::cmp;
+use std::env;
+use std::fmt::Debug;
 use std::fs::File;
+use std::io;
+use std::future::Future as StdFuture;
 use std::io::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 const DEFAULT_MAX_THREADS: usize = 4;
 
 const DEFAULT_MAX_MEMORY: usize = 4096 << 14;
 
-#[derive(Debug)]
+const DEFAULT_SHARD_DEPTH: usize = 4;
+
+const DEFAULT_STACK_SIZE: usize = 1 << 23;
+
+#[derive(Debug, Clone)]
 pub struct Builder {
     /// Max number of threads to check as part of the execution. This should be set as low as possible.
     pub max_threads: usize,
 
     /// Maximum amount of memory that can be consumed by the associated metadata.
+    ///
+    /// Memory of metadata that is dropped is reused, so only the metadata that
+    /// is alive at the same time counts towards the limit.
     pub max_memory: usize,
 
+    /// Map additional chunks of `max_memory` bytes instead of failing when the
+    /// metadata does not fit in `max_memory`.
+    pub grow_memory: bool,
+
     /// When doing an exhaustive fuzz, uses the file to store and load the fuzz
-    /// progress
+    /// progress. The checkpoint is also written when an execution fails.
+    ///
+    /// With more than one worker, the workers pause while the checkpoint is
+    /// written and it holds the unexplored path of each of them.
     pub checkpoint_file: Option<PathBuf>,
 
     /// How often to write the checkpoint file
@@ -28,23 +58,185 @@ pub struct Builder {
     /// What runtime to use
     pub runtime: Runtime,
 
+    /// Stack size of each model thread, in bytes. Only used by the generator,
+    /// fringe and ucontext runtimes.
+    ///
+    /// Overflowing the stack aborts the process, naming the model thread.
+    pub stack_size: usize,
+
     /// Log execution output to stdout.
     pub log: bool,
+
+    /// Number of OS threads exploring the execution tree in parallel.
+    pub workers: usize,
+
+    /// Only explore the part of the tree owned by shard `(index, count)`.
+    ///
+    /// When set, the checkpoint file name is suffixed with the shard.
+    pub shard: Option<(usize, usize)>,
+
+    /// Number of leading branch points the shards are split on.
+    pub shard_depth: usize,
+
+    /// Stop after running this many executions.
+    ///
+    /// When stopping early, the checkpoint file is written so that a later run
+    /// resumes where this one stopped.
+    pub max_iterations: Option<usize>,
+
+    /// Stop once exploring has taken this long.
+    ///
+    /// The limit is checked between executions.
+    pub time_limit: Option<Duration>,
+
+    /// Only explore executions that preempt running threads at most this many
+    /// times.
+    ///
+    /// Bounding preemptions makes the exploration incomplete, but most bugs
+    /// only need a few preemptions to show up.
+    pub max_preemptions: Option<usize>,
+
+    /// Run the single execution recorded in this file instead of exploring.
+    ///
+    /// The file has the format of a checkpoint or a printed failing path. Of
+    /// a checkpoint holding several paths, the first one is replayed.
+    pub replay_file: Option<PathBuf>,
+
+    /// DPOR algorithm used to prune equivalent executions
+    pub dpor: Dpor,
+
+    /// Stop exploring alternatives from program states that were already fully
+    /// explored.
+    ///
+    /// Not sound: executions may be missed even when the test registers all
+    /// relevant shared state. Disabled by default. See the `state` module.
+    pub cache_states: bool,
+
+    /// Skip reordering operations that are independent of the operations
+    /// they were already explored before. Disabled by default.
+    ///
+    /// Changes the order in which executions are explored, and so which
+    /// executions are within `max_preemptions` and which failure is found
+    /// first.
+    pub sleep_sets: bool,
+
+    /// Write the happens-before graph of the first failing execution to this
+    /// file, in the DOT format. When replaying, the graph of the replayed
+    /// execution is written whether it fails or not.
+    ///
+    /// Tracking the graph slows down every execution.
+    pub dot_file: Option<PathBuf>,
+
+    /// Write the timeline of the first failing execution to this file, as
+    /// Chrome trace-event JSON. Written under the same conditions as
+    /// `dot_file`.
+    pub trace_file: Option<PathBuf>,
+
+    /// Minimize the first failing execution and write its path to this file,
+    /// in the format read by `replay_file`.
+    ///
+    /// The failing execution is replayed with fewer preemptions and fewer
+    /// reads of stale values for as long as it keeps failing with the same
+    /// message. The DOT and trace files then describe the minimized execution.
+    pub shrink_file: Option<PathBuf>,
+}
+
+/// Summary of an exploration
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// Number of executions explored
+    pub executions: usize,
+
+    /// Largest number of branch points taken by a single execution
+    pub max_depth: usize,
+
+    /// Number of thread scheduling branch points explored
+    pub schedule_branches: usize,
+
+    /// Number of branch points picking the write read by an atomic load
+    pub write_branches: usize,
+
+    /// Number of branch points picking whether a timed park times out
+    pub timeout_branches: usize,
+
+    /// Number of branch points picking the task polled next by an executor
+    pub poll_branches: usize,
+
+    /// Number of branch points picking the leader released by a barrier
+    pub leader_branches: usize,
+
+    /// Total number of threads created across all executions
+    pub threads: usize,
+
+    /// Total number of objects created across all executions
+    pub objects: usize,
+
+    /// Time spent exploring
+    pub elapsed: Duration,
+
+    /// Largest amount of arena memory used by a single execution, in bytes
+    pub peak_memory: usize,
+
+    /// `false` when exploring stopped early because a budget was exhausted
+    pub complete: bool,
+
+    /// Estimated fraction of the execution tree that was explored
+    pub coverage: f64,
+}
+
+/// Dynamic partial order reduction algorithm
+#[derive(Debug, Clone, Copy)]
+pub enum Dpor {
+    /// Adds each racing thread as a backtrack point
+    Classic,
+
+    /// Optimal DPOR. A race inserts the sequence of steps reversing it into
+    /// the wakeup tree of the branch point, unless a thread asleep at the
+    /// branch point is able to start it or the tree already covers it.
+    /// Executions follow the wakeup trees, so each equivalent execution is
+    /// explored at most once.
+    ///
+    /// Always uses sleep sets. Once an execution schedules a thread that is in
+    /// the sleep set, the rest of it is known to be redundant and no longer
+    /// looked at for races.
+    Optimal,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Runtime {
     Thread,
     Generator,
     #[cfg(feature = "fringe")]
     Fringe,
+    /// Coroutines switched with `swapcontext`. Available on Linux.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    Ucontext,
+    /// A user provided runtime, created with the maximum number of threads
+    /// and the stack size. See the `scheduler` module.
+    Custom(fn(usize, usize) -> Box<scheduler::Scheduler>),
 }
 
 impl Builder {
+    /// Create a new `Builder`.
+    ///
+    /// The defaults may be overridden with the following environment variables:
+    ///
+    /// * `SYNCBOX_FUZZ_MAX_THREADS`
+    /// * `SYNCBOX_FUZZ_CHECKPOINT_FILE`
+    /// * `SYNCBOX_FUZZ_CHECKPOINT_INTERVAL`
+    /// * `SYNCBOX_FUZZ_RUNTIME`: one of `thread`, `generator`, `fringe` or
+    ///   `ucontext`
+    /// * `SYNCBOX_FUZZ_LOG`: set to `1` or `true` to log executions
+    /// * `SYNCBOX_FUZZ_MAX_PREEMPTIONS`
+    /// * `SYNCBOX_FUZZ_REPLAY_FILE`
+    /// * `SYNCBOX_FUZZ_DOT_FILE`
+    /// * `SYNCBOX_FUZZ_TRACE_FILE`
+    /// * `SYNCBOX_FUZZ_SHRINK_FILE`
     pub fn new() -> Builder {
-        Builder {
+        let mut builder = Builder {
             max_threads: DEFAULT_MAX_THREADS,
             max_memory: DEFAULT_MAX_MEMORY,
+            grow_memory: false,
             checkpoint_file: None,
             checkpoint_interval: 100_000,
 
@@ -54,8 +246,72 @@ impl Builder {
             #[cfg(not(feature = "fringe"))]
             runtime: Runtime::Generator,
 
+            stack_size: DEFAULT_STACK_SIZE,
             log: false,
+            workers: 1,
+            shard: None,
+            shard_depth: DEFAULT_SHARD_DEPTH,
+            max_iterations: None,
+            time_limit: None,
+            max_preemptions: None,
+            replay_file: None,
+            dpor: Dpor::Classic,
+            cache_states: false,
+            sleep_sets: false,
+            dot_file: None,
+            trace_file: None,
+            shrink_file: None,
+        };
+
+        builder.apply_env();
+        builder
+    }
+
+    /// Override the fields with the environment variables listed in `new`.
+    ///
+    /// This lets the environment take precedence over values set in code.
+    pub fn apply_env(&mut self) -> &mut Self {
+        if let Some(max_threads) = env_var("SYNCBOX_FUZZ_MAX_THREADS") {
+            self.max_threads = max_threads;
+        }
+
+        if let Some(file) = env::var_os("SYNCBOX_FUZZ_CHECKPOINT_FILE") {
+            self.checkpoint_file = Some(file.into());
+        }
+
+        if let Some(interval) = env_var("SYNCBOX_FUZZ_CHECKPOINT_INTERVAL") {
+            self.checkpoint_interval = interval;
+        }
+
+        if let Some(runtime) = env_var("SYNCBOX_FUZZ_RUNTIME") {
+            self.runtime = runtime;
+        }
+
+        if let Ok(log) = env::var("SYNCBOX_FUZZ_LOG") {
+            self.log = log == "1" || log == "true";
+        }
+
+        if let Some(max_preemptions) = env_var("SYNCBOX_FUZZ_MAX_PREEMPTIONS") {
+            self.max_preemptions = Some(max_preemptions);
+        }
+
+        if let Some(file) = env::var_os("SYNCBOX_FUZZ_REPLAY_FILE") {
+            self.replay_file = Some(file.into());
+        }
+
+        if let Some(file) = env::var_os("SYNCBOX_FUZZ_DOT_FILE") {
+            self.dot_file = Some(file.into());
+        }
+
+        if let Some(file) = env::var_os("SYNCBOX_FUZZ_TRACE_FILE") {
+            self.trace_file = Some(file.into());
         }
+
+        if let Some(file) = env::var_os("SYNCBOX_FUZZ_SHRINK_FILE") {
+            self.shrink_file = Some(file.into());
+        }
+
+        self
     }
 
     pub fn checkpoint_file(&mut self, file: &str) -> &mut Self {
@@ -63,64 +319,421 @@ impl Builder {
         self
     }
 
-    pub fn fuzz<F>(&self, f: F)
+    pub fn dot_file(&mut self, file: &str) -> &mut Self {
+        self.dot_file = Some(file.into());
+        self
+    }
+
+    pub fn trace_file(&mut self, file: &str) -> &mut Self {
+        self.trace_file = Some(file.into());
+        self
+    }
+
+    pub fn shrink_file(&mut self, file: &str) -> &mut Self {
+        self.shrink_file = Some(file.into());
+        self
+    }
+
+    pub fn stack_size(&mut self, size: usize) -> &mut Self {
+        assert!(size > 0, "stack size must be positive");
+        self.stack_size = size;
+        self
+    }
+
+    pub fn workers(&mut self, n: usize) -> &mut Self {
+        assert!(n > 0, "at least one worker is required");
+        self.workers = n;
+        self
+    }
+
+    pub fn shard(&mut self, index: usize, count: usize) -> &mut Self {
+        assert!(index < count, "shard index out of bounds");
+        self.shard = Some((index, count));
+        self
+    }
+
+    pub fn max_iterations(&mut self, n: usize) -> &mut Self {
+        self.max_iterations = Some(n);
+        self
+    }
+
+    pub fn time_limit(&mut self, limit: Duration) -> &mut Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    pub fn fuzz<F>(&self, f: F) -> Report
     where
         F: Fn() + Sync + Send + 'static,
     {
-        let mut execution = Execution::new(self.max_threads, self.max_memory);
-        let mut scheduler = match self.runtime {
-            Runtime::Thread => Scheduler::new_thread(self.max_threads),
-            Runtime::Generator => Scheduler::new_generator(self.max_threads),
-            #[cfg(feature = "fringe")]
-            Runtime::Fringe => Scheduler::new_fringe(self.max_threads),
-        };
+        let start = Instant::now();
+        let f = Arc::new(f);
 
-        if let Some(ref path) = self.checkpoint_file {
-            if path.exists() {
-                let mut file = File::open(path).unwrap();
-                let mut contents = String::new();
-                file.read_to_string(&mut contents).unwrap();
-                execution.path = serde_json::from_str(&contents).unwrap();
-            }
+        if let Some(ref file) = self.replay_file {
+            let mut report = self.replay(file, f);
+            report.elapsed = start.elapsed();
+            return report;
         }
 
-        execution.log = self.log;
+        let mut paths = self.initial_paths();
 
-        let f = Arc::new(f);
+        // A checkpoint written by several workers holds one path per worker
+        if self.workers > 1 || paths.len() != 1 {
+            let mut report = workers::fuzz(self, paths, f, start);
+            report.elapsed = start.elapsed();
+            return report;
+        }
 
+        let mut execution = Execution::new(self.max_threads, self.max_memory);
+        let mut scheduler = self.new_scheduler();
+
+        execution.path = paths.pop().unwrap();
+
+        execution.log = self.log;
+        execution.dpor = self.dpor;
+        execution.cache_states = self.cache_states;
+        execution.sleep_sets = self.sleep_sets;
+        execution.graph = self.new_graph();
+        execution.timeline = self.new_timeline();
+        execution.arena.set_growable(self.grow_memory);
+
+        let mut report = Report::default();
         let mut i = 0;
 
         loop {
+            if self.budget_exhausted(i, start) {
+                self.write_checkpoint(&execution.path);
+
+                report.coverage = execution.path.coverage();
+                report.elapsed = start.elapsed();
+                report.print_stopped();
+                return report;
+            }
+
             i += 1;
 
             if i % self.checkpoint_interval == 0 {
                 println!(" ===== iteration {} =====", i);
+                self.write_checkpoint(&execution.path);
+            }
+
+            let body = f.clone();
+
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                scheduler.run(&mut execution, move || {
+                    body();
+                    rt::thread_done();
+                });
+            }));
 
-                if let Some(ref path) = self.checkpoint_file {
-                    let serialized = serde_json::to_string(&execution.path).unwrap();
+            if let Err(err) = res {
+                self.write_checkpoint(&execution.path);
 
-                    let mut file = File::create(path).unwrap();
-                    file.write_all(serialized.as_bytes()).unwrap();
-                }
+                println!(" ===== failing path =====");
+                println!("{}", serde_json::to_string(&execution.path).unwrap());
+
+                self.failed(&execution, &f, &err);
+                panic::resume_unwind(err);
+            }
+
+            report.record(&execution);
+
+            if let Some(next) = execution.step() {
+                execution = next;
+            } else {
+                break;
             }
+        }
+
+        report.complete = true;
+        report.coverage = 1.0;
+        report.elapsed = start.elapsed();
+        report
+    }
+
+    pub fn block_on<F, R>(&self, f: F) -> Report
+    where
+        F: Fn() -> R + Sync + Send + 'static,
+        R: StdFuture<Output = ()>,
+    {
+        self.fuzz(move || rt::block_on(f()))
+    }
 
-            let f = f.clone();
+    pub(crate) fn new_scheduler(&self) -> Scheduler {
+        match self.runtime {
+            Runtime::Thread => Scheduler::new_thread(self.max_threads),
+            Runtime::Generator => Scheduler::new_generator(self.max_threads, self.stack_size),
+            #[cfg(feature = "fringe")]
+            Runtime::Fringe => Scheduler::new_fringe(self.max_threads, self.stack_size),
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            Runtime::Ucontext => Scheduler::new_ucontext(self.max_threads, self.stack_size),
+            Runtime::Custom(new) => Scheduler::new_custom(new(self.max_threads, self.stack_size)),
+        }
+    }
 
+    fn replay<F>(&self, file: &PathBuf, f: Arc<F>) -> Report
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        let mut execution = Execution::new(self.max_threads, self.max_memory);
+        let mut scheduler = self.new_scheduler();
+
+        execution.path = read_paths(file).into_iter().next()
+            .expect("no path to replay");
+        execution.path.rewind();
+        execution.log = self.log;
+        execution.dpor = self.dpor;
+        execution.cache_states = self.cache_states;
+        execution.sleep_sets = self.sleep_sets;
+        execution.graph = self.new_graph();
+        execution.timeline = self.new_timeline();
+        execution.arena.set_growable(self.grow_memory);
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
             scheduler.run(&mut execution, move || {
                 f();
                 rt::thread_done();
             });
+        }));
 
-            if let Some(next) = execution.step() {
-                execution = next;
-            } else {
+        self.export(&execution);
+
+        if let Err(err) = res {
+            panic::resume_unwind(err);
+        }
+
+        let mut report = Report::default();
+        report.record(&execution);
+        report
+    }
+
+    pub(crate) fn new_graph(&self) -> Option<Graph> {
+        self.dot_file.as_ref().map(|_| Graph::new())
+    }
+
+    pub(crate) fn new_timeline(&self) -> Option<Timeline> {
+        self.trace_file.as_ref().map(|_| Timeline::new())
+    }
+
+    /// Shrink the failing `execution` and write the files describing it
+    pub(crate) fn failed<F>(&self, execution: &Execution, f: &Arc<F>, err: &Box<Any + Send>)
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        let file = match self.shrink_file {
+            Some(ref file) => file,
+            None => {
+                self.export(execution);
                 return;
             }
+        };
+
+        let shrunk = shrink::shrink(self, &execution.path, f, shrink::message(err));
+        let execution = shrunk.as_ref().unwrap_or(execution);
+
+        let serialized = serde_json::to_string(&execution.path).unwrap();
+        write_file(file, |out| out.write_all(serialized.as_bytes()));
+
+        self.export(execution);
+    }
+
+    /// Write the happens-before graph and the timeline of `execution` to the
+    /// DOT and trace files, if any. This runs while a failure is reported, so
+    /// I/O errors are printed instead of replacing the failure.
+    pub(crate) fn export(&self, execution: &Execution) {
+        if let (Some(file), Some(graph)) = (self.dot_file.as_ref(), execution.graph.as_ref()) {
+            write_file(file, |out| graph.write_dot(out));
+        }
+
+        if let (Some(file), Some(timeline)) = (self.trace_file.as_ref(), execution.timeline.as_ref()) {
+            write_file(file, |out| timeline.write_json(out));
+        }
+    }
+
+    /// Returns `true` once `iterations` executions or the time limit have been
+    /// used up.
+    pub(crate) fn budget_exhausted(&self, iterations: usize, start: Instant) -> bool {
+        if let Some(max) = self.max_iterations {
+            if iterations >= max {
+                return true;
+            }
+        }
+
+        if let Some(limit) = self.time_limit {
+            if start.elapsed() >= limit {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Paths to resume exploring from
+    fn initial_paths(&self) -> Vec<Path> {
+        let mut paths = self.load_checkpoint().unwrap_or_else(|| vec![Path::new()]);
+
+        for path in &mut paths {
+            // The path of a failing execution is written as it was run
+            path.rewind();
+
+            if let Some((index, count)) = self.shard {
+                path.set_shard(Shard {
+                    index,
+                    count,
+                    depth: self.shard_depth,
+                });
+            }
+
+            path.set_preemption_bound(self.max_preemptions);
+        }
+
+        paths
+    }
+
+    fn load_checkpoint(&self) -> Option<Vec<Path>> {
+        let path = match self.checkpoint_path() {
+            Some(path) => path,
+            None => return None,
+        };
+
+        if !path.exists() {
+            return None;
+        }
+
+        Some(read_paths(&path))
+    }
+
+    /// Write `checkpoint`, either a single path or a list of paths, to the
+    /// checkpoint file, if any
+    pub(crate) fn write_checkpoint<T: Serialize>(&self, checkpoint: &T) {
+        if let Some(ref file) = self.checkpoint_path() {
+            let serialized = serde_json::to_string(checkpoint).unwrap();
+
+            let mut file = File::create(file).unwrap();
+            file.write_all(serialized.as_bytes()).unwrap();
+        }
+    }
+
+    /// Each shard has its own checkpoint file
+    fn checkpoint_path(&self) -> Option<PathBuf> {
+        let path = self.checkpoint_file.as_ref()?;
+
+        match self.shard {
+            Some((index, count)) => {
+                let mut path = path.clone().into_os_string();
+                path.push(format!(".shard-{}-of-{}", index, count));
+                Some(path.into())
+            }
+            None => Some(path.clone()),
+        }
+    }
+}
+
+impl FromStr for Runtime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Runtime, String> {
+        match s {
+            "thread" => Ok(Runtime::Thread),
+            "generator" => Ok(Runtime::Generator),
+            #[cfg(feature = "fringe")]
+            "fringe" => Ok(Runtime::Fringe),
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            "ucontext" => Ok(Runtime::Ucontext),
+            _ => Err(format!("unknown runtime `{}`", s)),
+        }
+    }
+}
+
+/// Contents of a checkpoint file. Exploring with several workers leaves
+/// several unexplored paths.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Checkpoint {
+    Path(Path),
+    Paths(Vec<Path>),
+}
+
+fn read_paths(file: &PathBuf) -> Vec<Path> {
+    let mut file = File::open(file).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+
+    match serde_json::from_str(&contents).unwrap() {
+        Checkpoint::Path(path) => vec![path],
+        Checkpoint::Paths(paths) => paths,
+    }
+}
+
+/// Parse the environment variable `name`, if it is set
+fn env_var<T>(name: &str) -> Option<T>
+where
+    T: FromStr,
+    T::Err: Debug,
+{
+    let val = env::var(name).ok()?;
+
+    match val.parse() {
+        Ok(val) => Some(val),
+        Err(err) => panic!("invalid value for {}: {:?}", name, err),
+    }
+}
+
+/// Create `path` and write to it with `f`, printing any error
+fn write_file<F>(path: &PathBuf, f: F)
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let res = File::create(path).and_then(|mut file| f(&mut file));
+
+    if let Err(err) = res {
+        eprintln!("failed to write {}: {}", path.display(), err);
+    }
+}
+
+impl Report {
+    /// Record the statistics of a completed execution
+    pub(crate) fn record(&mut self, execution: &Execution) {
+        for kind in execution.path.new_branches() {
+            match kind {
+                Kind::Schedule => self.schedule_branches += 1,
+                Kind::Load(_) => self.write_branches += 1,
+                Kind::Timeout => self.timeout_branches += 1,
+                Kind::Poll => self.poll_branches += 1,
+                Kind::Leader(_) => self.leader_branches += 1,
+            }
         }
+
+        self.executions += 1;
+        self.max_depth = cmp::max(self.max_depth, execution.path.pos());
+        self.threads += execution.threads.len();
+        self.objects += execution.objects.len();
+        self.peak_memory = cmp::max(self.peak_memory, execution.arena.peak());
+    }
+
+    pub(crate) fn print_stopped(&self) {
+        println!(
+            " ===== stopped after {} iterations; ~{:.1}% explored =====",
+            self.executions,
+            self.coverage * 100.0);
+    }
+
+    /// Combine the statistics of two reports
+    pub(crate) fn merge(&mut self, other: &Report) {
+        self.executions += other.executions;
+        self.max_depth = cmp::max(self.max_depth, other.max_depth);
+        self.schedule_branches += other.schedule_branches;
+        self.write_branches += other.write_branches;
+        self.timeout_branches += other.timeout_branches;
+        self.poll_branches += other.poll_branches;
+        self.leader_branches += other.leader_branches;
+        self.threads += other.threads;
+        self.objects += other.objects;
+        self.peak_memory = cmp::max(self.peak_memory, other.peak_memory);
     }
 }
 
-pub fn fuzz<F>(f: F)
+pub fn fuzz<F>(f: F) -> Report
 where
     F: Fn() + Sync + Send + 'static,
 {
@@ -131,20 +744,20 @@ if_futures! {
     use _futures::Future;
 
     impl Builder {
-        pub fn fuzz_future<F, R>(&self, f: F)
+        pub fn fuzz_future<F, R>(&self, f: F) -> Report
         where
             F: Fn() -> R + Sync + Send + 'static,
             R: Future<Item = (), Error = ()>,
         {
-            self.fuzz(move || rt::wait_future(f()));
+            self.fuzz(move || rt::wait_future(f()))
         }
     }
 
-    pub fn fuzz_future<F, R>(f: F)
+    pub fn fuzz_future<F, R>(f: F) -> Report
     where
         F: Fn() -> R + Sync + Send + 'static,
         R: Future<Item = (), Error = ()>,
     {
-        Builder::new().fuzz_future(f);
+        Builder::new().fuzz_future(f)
     }
 }
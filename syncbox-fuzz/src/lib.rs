@@ -10,6 +10,7 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+extern crate syncbox_fuzz_macros;
 
 #[cfg(feature = "fringe")]
 extern crate fringe;
@@ -34,12 +35,19 @@ macro_rules! debug {
 }
 
 pub mod fuzz;
+pub mod future;
 mod rt;
+pub mod scheduler;
+mod shrink;
+pub mod state;
 pub mod sync;
 pub mod thread;
+pub mod time;
+mod workers;
 // mod util;
 
 pub use fuzz::fuzz;
+pub use syncbox_fuzz_macros::test;
 
 if_futures! {
     extern crate futures as _futures;
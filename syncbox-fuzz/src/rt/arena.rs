@@ -1,8 +1,9 @@
 #![allow(warnings)]
 
 use std::alloc::Layout;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::cmp;
+use std::collections::HashMap;
 use std::fmt;
 use std::ops::{Deref, DerefMut};
 use std::ptr;
@@ -17,51 +18,78 @@ pub struct Arena {
 pub struct Slice<T> {
     ptr: *mut T,
     len: usize,
-    _inner: Rc<Inner>,
+    inner: Rc<Inner>,
 }
 
 #[derive(Debug)]
 struct Inner {
-    /// Head of the arena space
-    head: *mut u8,
+    /// Mapped regions. Only the first one exists unless the arena is growable.
+    chunks: RefCell<Vec<Chunk>>,
+
+    /// Index of the chunk currently allocated from
+    current: Cell<usize>,
 
-    /// Offset into the last region
+    /// Offset into the current chunk
     pos: Cell<usize>,
 
-    /// Total capacity of the arena
+    /// Bytes allocated from the chunks before the current one
+    filled: Cell<usize>,
+
+    /// Memory of dropped slices, by size and alignment, to be reused
+    free: RefCell<HashMap<(usize, usize), Vec<*mut u8>>>,
+
+    /// Bytes held by `free`
+    freed: Cell<usize>,
+
+    /// Largest number of bytes in use since the arena was cleared
+    peak: Cell<usize>,
+
+    /// Map additional chunks instead of failing when the arena is exhausted
+    growable: Cell<bool>,
+
+    /// Branch depth of the execution, reported when the arena is exhausted
+    depth: Cell<usize>,
+}
+
+#[derive(Debug)]
+struct Chunk {
+    /// Head of the chunk space
+    head: *mut u8,
+
+    /// Capacity of the chunk
     cap: usize,
 }
 
 impl Arena {
     /// Create an `Arena` with specified capacity.
     ///
-    /// Capacity must be a power of 2. The capacity cannot be grown after the fact.
+    /// Capacity must be a power of 2. Unless the arena is made growable, the
+    /// capacity cannot be grown after the fact.
     pub fn with_capacity(capacity: usize) -> Arena {
-        let head = unsafe {
-            libc::mmap(
-                ptr::null_mut(),
-                capacity,
-                libc::PROT_READ | libc::PROT_WRITE,
-                libc::MAP_ANON | libc::MAP_PRIVATE,
-                -1,
-                0,
-            )
-        };
-
         Arena {
-            inner: Rc::new(Inner {
-                head: head as *mut u8,
-                pos: Cell::new(0),
-                cap: capacity,
-            }),
+            inner: Rc::new(Inner::new(capacity, false)),
         }
     }
 
-    pub fn slice<T>(&mut self, len: usize) -> Slice<T>
+    /// When `growable` is set, additional chunks of the initial capacity are
+    /// mapped once the arena is exhausted.
+    pub fn set_growable(&mut self, growable: bool) {
+        self.inner.growable.set(growable);
+    }
+
+    /// Set the branch depth reported when the arena is exhausted
+    pub fn set_depth(&mut self, depth: usize) {
+        self.inner.depth.set(depth);
+    }
+
+    /// Allocate `len` default values.
+    ///
+    /// Panics when the arena is exhausted and not growable.
+    pub fn slice<T>(&self, len: usize) -> Slice<T>
     where
         T: Default,
     {
-        let ptr: *mut T = self.allocate(len);
+        let ptr: *mut T = self.inner.allocate(len);
 
         for i in 0..len {
             unsafe {
@@ -72,44 +100,177 @@ impl Arena {
         Slice {
             ptr,
             len,
-            _inner: self.inner.clone(),
+            inner: self.inner.clone(),
         }
     }
 
+    /// Number of bytes currently allocated
+    pub fn used(&self) -> usize {
+        self.inner.used()
+    }
+
+    /// Largest number of bytes allocated at once since the arena was cleared
+    pub fn peak(&self) -> usize {
+        self.inner.peak.get()
+    }
+
+    /// Release all allocations. Chunks stay mapped to be reused.
     pub fn clear(&mut self) {
-        assert!(1 == Rc::strong_count(&self.inner));
-        self.inner.pos.set(0);
+        let inner = &self.inner;
+
+        inner.depth.set(0);
+
+        if 1 == Rc::strong_count(inner) {
+            inner.current.set(0);
+            inner.pos.set(0);
+            inner.filled.set(0);
+            inner.free.borrow_mut().clear();
+            inner.freed.set(0);
+            inner.peak.set(0);
+            return;
+        }
+
+        // Some allocations outlive the execution. Their memory stays in use
+        // until they are dropped, and the next execution allocates around it.
+        inner.peak.set(inner.used());
+    }
+}
+
+impl Inner {
+    fn new(capacity: usize, growable: bool) -> Inner {
+        Inner {
+            chunks: RefCell::new(vec![Chunk::map(capacity)]),
+            current: Cell::new(0),
+            pos: Cell::new(0),
+            filled: Cell::new(0),
+            free: RefCell::new(HashMap::new()),
+            freed: Cell::new(0),
+            peak: Cell::new(0),
+            growable: Cell::new(growable),
+            depth: Cell::new(0),
+        }
+    }
+
+    fn used(&self) -> usize {
+        self.filled.get() + self.pos.get() - self.freed.get()
+    }
+
+    fn record_peak(&self) {
+        self.peak.set(cmp::max(self.peak.get(), self.used()));
+    }
+
+    /// Make the memory of a dropped slice available to allocations of the
+    /// same size and alignment
+    fn release(&self, ptr: *mut u8, size: usize, align: usize) {
+        if size == 0 {
+            return;
+        }
+
+        self.free.borrow_mut()
+            .entry((size, align))
+            .or_insert_with(Vec::new)
+            .push(ptr);
+
+        self.freed.set(self.freed.get() + size);
+    }
+
+    fn capacity(&self) -> usize {
+        self.chunks.borrow().iter().map(|chunk| chunk.cap).sum()
     }
 
-    fn allocate<T>(&mut self, count: usize) -> *mut T {
+    fn allocate<T>(&self, count: usize) -> *mut T {
         let layout = Layout::new::<T>();
         let mask = layout.align() - 1;
-        let pos = self.inner.pos.get();
+        let size = layout.size() * count;
 
-        debug_assert!(layout.align() >= (pos & mask));
+        let reused = self.free.borrow_mut()
+            .get_mut(&(size, layout.align()))
+            .and_then(Vec::pop);
 
-        let mut skip = layout.align() - (pos & mask);
-
-        if skip == layout.align() {
-            skip = 0;
+        if let Some(ptr) = reused {
+            self.freed.set(self.freed.get() - size);
+            return ptr as *mut T;
         }
 
-        let additional = skip + layout.size() * count;
+        loop {
+            let pos = self.pos.get();
+            let (head, cap) = {
+                let chunks = self.chunks.borrow();
+                let chunk = &chunks[self.current.get()];
+                (chunk.head, chunk.cap)
+            };
+
+            let mut skip = layout.align() - (pos & mask);
+
+            if skip == layout.align() {
+                skip = 0;
+            }
+
+            if pos + skip + size <= cap {
+                self.pos.set(pos + skip + size);
 
-        assert!(pos + additional <= self.inner.cap, "arena overflow");
+                let ret = unsafe { head.offset((pos + skip) as isize) as *mut T };
 
-        self.inner.pos.set(pos + additional);
+                debug_assert!((ret as usize) >= head as usize);
+                debug_assert!(size == 0 || (ret as usize) < (head as usize + cap));
 
-        let ret = unsafe { self.inner.head.offset((pos + skip) as isize) as *mut T };
+                self.record_peak();
+                return ret;
+            }
+
+            if !self.growable.get() {
+                panic!(
+                    "arena exhausted at branch depth {}; requested {} bytes \
+                     with {} of {} bytes in use; increase \
+                     `Builder::max_memory` or enable `Builder::grow_memory`",
+                    self.depth.get(),
+                    size,
+                    self.used(),
+                    self.capacity());
+            }
 
-        debug_assert!((ret as usize) >= self.inner.head as usize);
-        debug_assert!((ret as usize) < (self.inner.head as usize + self.inner.cap));
+            // Move on to the next chunk, mapping it if needed. Chunks are at
+            // least as large as the first one.
+            let next = self.current.get() + 1;
+            let mut chunks = self.chunks.borrow_mut();
+
+            if next == chunks.len() {
+                let cap = cmp::max(chunks[0].cap, size + layout.align());
+                chunks.push(Chunk::map(cap));
+            }
 
-        ret
+            self.filled.set(self.filled.get() + pos);
+            self.current.set(next);
+            self.pos.set(0);
+        }
     }
 }
 
-impl Drop for Inner {
+impl Chunk {
+    fn map(cap: usize) -> Chunk {
+        let head = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                cap,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_ANON | libc::MAP_PRIVATE,
+                -1,
+                0,
+            )
+        };
+
+        if head == libc::MAP_FAILED {
+            panic!("failed to map {} bytes for the arena", cap);
+        }
+
+        Chunk {
+            head: head as *mut u8,
+            cap,
+        }
+    }
+}
+
+impl Drop for Chunk {
     fn drop(&mut self) {
         let res = unsafe { libc::munmap(self.head as *mut libc::c_void, self.cap) };
 
@@ -118,9 +279,9 @@ impl Drop for Inner {
     }
 }
 
-impl<T: Clone> Slice<T> {
-    pub fn clone_with(&self, arena: &mut Arena) -> Slice<T> {
-        let ptr: *mut T = arena.allocate(self.len);
+impl<T: Clone> Clone for Slice<T> {
+    fn clone(&self) -> Slice<T> {
+        let ptr: *mut T = self.inner.allocate(self.len);
 
         for i in 0..self.len {
             unsafe {
@@ -131,7 +292,7 @@ impl<T: Clone> Slice<T> {
         Slice {
             ptr,
             len: self.len,
-            _inner: arena.inner.clone(),
+            inner: self.inner.clone(),
         }
     }
 }
@@ -177,5 +338,8 @@ impl<T> Drop for Slice<T> {
                 ptr::read(self.ptr.offset(i as isize) as *const _);
             }
         }
+
+        let layout = Layout::new::<T>();
+        self.inner.release(self.ptr as *mut u8, layout.size() * self.len, layout.align());
     }
 }
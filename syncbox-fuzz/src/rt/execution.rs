@@ -1,10 +1,20 @@
+use fuzz::Dpor;
 use rt::Path;
 use rt::arena::Arena;
+use rt::graph::{Edge, Graph};
 use rt::object;
 use rt::thread;
+use rt::timeline::Timeline;
 use rt::vv::VersionVec;
+use rt::wakeup::{Sequence, Step};
+use state::State;
 
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
 
 pub struct Execution {
     /// Execution path taken
@@ -26,8 +36,51 @@ pub struct Execution {
 
     pub max_history: usize,
 
+    /// Virtual time elapsed since the start of the execution.
+    ///
+    /// Time only advances when all threads are blocked or when a timeout is
+    /// explored as a branch.
+    pub time: Duration,
+
     /// Log execution output to STDOUT
     pub log: bool,
+
+    /// DPOR algorithm used to find races
+    pub dpor: Dpor,
+
+    /// Steps taken so far, in order. Only tracked for optimal DPOR.
+    events: Vec<Event>,
+
+    /// Races already reversed by the execution: the branch point of the
+    /// first step, the racing thread and the number of steps it has taken
+    reversed: HashSet<(usize, usize, usize)>,
+
+    /// Skip exploring program states that were already fully explored
+    pub cache_states: bool,
+
+    /// Put threads that were already explored from a branch point to sleep
+    pub sleep_sets: bool,
+
+    /// Shared state registered by the test, included in the state hash
+    pub states: Vec<Arc<State>>,
+
+    /// Happens-before graph of the execution, when tracked
+    pub graph: Option<Graph>,
+
+    /// Timeline of the execution, when tracked
+    pub timeline: Option<Timeline>,
+}
+
+/// A step taken by a thread
+#[derive(Debug)]
+struct Event {
+    /// Branch point at which the step was scheduled
+    path_id: usize,
+
+    step: Step,
+
+    /// DPOR clock of the thread after taking the step
+    dpor_vv: VersionVec,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -39,27 +92,39 @@ impl Execution {
     /// This is only called at the start of a fuzz run. The same instance is
     /// reused across permutations.
     pub fn new(max_threads: usize, max_memory: usize) -> Execution {
+        let arena = Arena::with_capacity(max_memory);
         let mut threads = thread::Set::new(max_threads);
 
         // Create the root thread
-        threads.new_thread();
+        threads.new_thread(&arena);
+
+        let seq_cst_causality = VersionVec::new(max_threads, &arena);
 
         Execution {
             // id: Id::new(),
             path: Path::new(),
             threads,
             objects: object::Set::new(),
-            seq_cst_causality: VersionVec::new(max_threads),
-            arena: Arena::with_capacity(max_memory),
+            seq_cst_causality,
+            arena,
             max_threads,
             max_history: 7,
+            time: Duration::from_millis(0),
             log: false,
+            dpor: Dpor::Classic,
+            events: vec![],
+            reversed: HashSet::new(),
+            cache_states: false,
+            sleep_sets: false,
+            states: vec![],
+            graph: None,
+            timeline: None,
         }
     }
 
     /// Create state to track a new thread
     pub fn new_thread(&mut self) -> thread::Id {
-        let thread_id = self.threads.new_thread();
+        let thread_id = self.threads.new_thread(&self.arena);
 
         let (active, new) = self.threads.active2_mut(thread_id);
 
@@ -70,6 +135,9 @@ impl Execution {
         // harmful even if not needed.
         new.causality[thread_id] += 1;
 
+        let spawn = self.trace(|| format!("spawn thread {}", thread_id.as_usize()));
+        self.trace_edge_to_next(spawn, thread_id, Edge::Spawn);
+
         thread_id
     }
 
@@ -78,29 +146,73 @@ impl Execution {
             return;
         }
 
+        self.timeline_instant(|| format!("unpark thread {}", id.as_usize()));
+
+        let dpor = self.dpor;
+
         // Synchronize memory
         let (active, th) = self.threads.active2_mut(id);
         th.causality.join(&active.causality);
 
         if th.is_blocked() || th.is_yield() {
             th.set_runnable();
+            th.deadline = None;
+
+            // The steps of the woken thread cannot be moved before the step
+            // that woke it.
+            if let Dpor::Optimal = dpor {
+                th.dpor_vv.join(&active.dpor_vv);
+            }
+        }
+    }
+
+    /// Advance the virtual clock, unblocking all threads whose deadline has
+    /// been reached.
+    pub fn advance_time(&mut self, to: Duration) {
+        if to > self.time {
+            self.time = to;
+        }
+
+        let now = self.time;
+
+        for (_, th) in self.threads.iter_mut() {
+            match th.deadline {
+                Some(deadline) if deadline <= now => {}
+                _ => continue,
+            }
+
+            th.deadline = None;
+            th.timed_out = true;
+            th.set_runnable();
         }
     }
 
     /// Resets the execution state for the next execution run
     pub fn step(self) -> Option<Self> {
+        // Registered state may hold objects that use the arena
+        drop(self.states);
+
         let max_threads = self.max_threads;
         let max_history = self.max_history;
         let log = self.log;
+        let dpor = self.dpor;
+        let cache_states = self.cache_states;
+        let sleep_sets = self.sleep_sets;
+        let mut events = self.events;
+        let mut reversed = self.reversed;
+        let mut graph = self.graph;
+        let mut timeline = self.timeline;
         let mut arena = self.arena;
         let mut path = self.path;
         let mut objects = self.objects;
 
         let mut threads = self.threads;
 
+        // Drop everything allocated in the arena before clearing it
         objects.clear();
-
-        // Force dropping the rest of the fields here
+        threads.clear();
+        events.clear();
+        reversed.clear();
         drop(self.seq_cst_causality);
 
         arena.clear();
@@ -109,10 +221,17 @@ impl Execution {
             return None;
         }
 
-        threads.clear();
-        threads.new_thread();
+        threads.new_thread(&arena);
+
+        if let Some(ref mut graph) = graph {
+            graph.clear();
+        }
+
+        if let Some(ref mut timeline) = timeline {
+            timeline.clear();
+        }
 
-        let seq_cst_causality = VersionVec::new(max_threads);
+        let seq_cst_causality = VersionVec::new(max_threads, &arena);
 
         Some(Execution {
             path,
@@ -122,7 +241,16 @@ impl Execution {
             arena,
             max_threads,
             max_history,
+            time: Duration::from_millis(0),
             log,
+            dpor,
+            events,
+            reversed,
+            cache_states,
+            sleep_sets,
+            states: vec![],
+            graph,
+            timeline,
         })
     }
 
@@ -134,41 +262,94 @@ impl Execution {
 
         let curr_thread = self.threads.active_id();
 
+        // When no thread is able to make progress, time passes until the
+        // earliest deadline.
+        if !self.threads.iter().any(|(_, th)| th.is_runnable()) {
+            let next_deadline = self.threads.iter()
+                .filter_map(|(_, th)| th.deadline)
+                .min();
+
+            if let Some(deadline) = next_deadline {
+                self.advance_time(deadline);
+            }
+        }
+
+        // Races found by redundant executions are also found by the
+        // executions they are equivalent to.
+        let redundant = self.path.is_redundant();
+        let mut races = vec![];
+
         for (th_id, th) in self.threads.iter() {
             let operation = match th.operation {
-                Some(operation) => operation,
-                None => continue,
+                Some(operation) if !redundant => operation,
+                _ => continue,
             };
 
             for access in self.objects.last_dependent_accesses(operation) {
-                if access.dpor_vv <= th.dpor_vv {
+                if access.dpor_vv.precedes(&th.dpor_vv) {
                     // The previous access happened before this access, thus
                     // there is no race.
                     continue;
                 }
 
-                self.path.schedule_mut(access.path_id).backtrack(th_id);
+                match self.dpor {
+                    Dpor::Classic => self.path.backtrack(access.path_id, th_id),
+                    Dpor::Optimal => races.push((access.path_id, th_id)),
+                }
             }
         }
 
-        let mut initial = Some(self.threads.active_id());
+        for (path_id, th_id) in races {
+            self.reverse_race(path_id, th_id);
+        }
+
+        let mut current = Some(self.threads.active_id());
 
         if !self.threads.active().is_runnable() {
-            initial = None;
+            current = None;
         }
 
+        // Prefer threads that are not in the sleep set. If every runnable
+        // thread is asleep, the execution is redundant but still has to run
+        // to completion.
+        let initial = current
+            .filter(|&id| !self.threads[id].sleeping)
+            .or_else(|| {
+                self.threads.iter()
+                    .find(|&(_, th)| th.is_runnable() && !th.sleeping)
+                    .map(|(i, _)| i)
+            })
+            .or(current)
+            .or_else(|| {
+                self.threads.iter()
+                    .find(|&(_, th)| th.is_runnable())
+                    .map(|(i, _)| i)
+            });
+
         let path_id = self.path.pos();
+        self.arena.set_depth(path_id);
+
+        let state = if self.cache_states && self.path.is_new_branch() && !self.path.is_pruned() {
+            Some(self.hash_state())
+        } else {
+            None
+        };
+
+        // All executions from this state have already been explored. The
+        // rest of the execution explores no alternatives, but its races with
+        // earlier branch points are still backtracked.
+        if state.map(|state| self.path.is_explored(state)).unwrap_or(false) {
+            self.path.set_explored_from(path_id);
+        }
 
-        let next = self.path.branch_thread({
+        let next = self.path.branch_thread(current, {
             self.threads.iter().map(|(i, th)| {
-                if initial.is_none() && th.is_runnable() {
-                    initial = Some(i);
-                }
-
                 if initial == Some(i) {
                     Thread::Active
                 } else if !th.is_runnable() {
                     Thread::Disabled
+                } else if th.sleeping {
+                    Thread::Sleep
                 } else {
                     Thread::Skip
                 }
@@ -183,13 +364,48 @@ impl Execution {
             let terminal = self.threads.iter()
                 .all(|(_, th)| th.is_terminated());
 
+            if !terminal {
+                self.check_lost_wakeup();
+            }
+
             assert!(terminal, "deadlock");
             return true;
         }
 
-        if let Some(operation) = self.threads.active().operation {
+        // Only optimal DPOR prunes the rest of an execution that is known to
+        // be redundant.
+        if self.threads.active().sleeping {
+            if let Dpor::Optimal = self.dpor {
+                self.path.set_redundant();
+            }
+        }
+
+        // The state is only explored once all of its continuations are.
+        if let Some(state) = state {
+            if !self.path.is_pruned() {
+                self.path.set_state(path_id, state);
+            }
+        }
+
+        let sleep_sets = match self.dpor {
+            Dpor::Classic => self.sleep_sets,
+            Dpor::Optimal => true,
+        };
+
+        if sleep_sets {
+            self.update_sleep_set(path_id);
+        }
+
+        let operation = self.threads.active().operation;
+        let th_id = self.threads.active_id();
+
+        let optimal = match self.dpor {
+            Dpor::Classic => false,
+            Dpor::Optimal => true,
+        };
+
+        if let Some(operation) = operation {
             let threads = &mut self.threads;
-            let th_id = threads.active_id();
 
             for access in self.objects.last_dependent_accesses(operation) {
                 threads.active_mut().dpor_vv.join(&access.dpor_vv);
@@ -202,6 +418,23 @@ impl Execution {
                 path_id: path_id,
                 dpor_vv: threads.active().dpor_vv.clone(),
             });
+
+            if let (Some(timeline), Some(access)) = (self.timeline.as_mut(), operation.atomic_access()) {
+                let name = format!("atomic {}: {}", operation.object_id().as_usize(), access);
+                timeline.instant(th_id, path_id, name);
+            }
+        } else if optimal {
+            // Optimal DPOR orders every step, including the ones that perform
+            // no operation, as it reorders whole sequences of steps.
+            self.threads.active_mut().dpor_vv[th_id] += 1;
+        }
+
+        if optimal {
+            self.events.push(Event {
+                path_id,
+                step: step(th_id, operation),
+                dpor_vv: self.threads.active().dpor_vv.clone(),
+            });
         }
 
         if self.log {
@@ -211,18 +444,271 @@ impl Execution {
         curr_thread != self.threads.active_id()
     }
 
+    /// Reverse the race between the step taken at the branch point `path_id`
+    /// and the next operation of `th_id`, for optimal DPOR.
+    ///
+    /// The steps that follow the first racing step without happening after it
+    /// are inserted into the wakeup tree of its branch point, followed by the
+    /// operation of `th_id`. Nothing is inserted if a thread in the sleep set
+    /// of the branch point is able to start the sequence: an equivalent
+    /// execution has been explored already.
+    fn reverse_race(&mut self, path_id: usize, th_id: thread::Id) {
+        let th = &self.threads[th_id];
+
+        if !self.reversed.insert((path_id, th_id.as_usize(), th.dpor_vv[th_id])) {
+            return;
+        }
+
+        let first = match self.events.binary_search_by_key(&path_id, |event| event.path_id) {
+            Ok(first) => first,
+            Err(_) => return,
+        };
+
+        let events = &self.events[first..];
+        let next = step(th_id, th.operation);
+
+        let ordered = |a: &Step, a_vv: &VersionVec, b: &Step, b_vv: &VersionVec| {
+            let thread = thread::Id::from_usize(a.thread);
+            a.thread == b.thread || a.is_dependent(b) || a_vv[thread] <= b_vv[thread]
+        };
+
+        // Steps that happen after the first racing step
+        let mut after = vec![true];
+
+        for j in 1..events.len() {
+            let later = (0..j).any(|i| {
+                after[i] && ordered(&events[i].step, &events[i].dpor_vv, &events[j].step, &events[j].dpor_vv)
+            });
+
+            after.push(later);
+        }
+
+        // The operation happens after the first racing step through other
+        // steps. Those races are reversed first.
+        let indirect = (1..events.len()).any(|i| {
+            after[i] && ordered(&events[i].step, &events[i].dpor_vv, &next, &th.dpor_vv)
+        });
+
+        if indirect {
+            return;
+        }
+
+        let (mut steps, mut vvs): (Vec<_>, Vec<_>) = events.iter()
+            .zip(after.iter())
+            .filter(|&(_, &after)| !after)
+            .map(|(event, _)| (event.step, &event.dpor_vv))
+            .unzip();
+
+        steps.push(next);
+        vvs.push(&th.dpor_vv);
+
+        let sequence = {
+            let steps = &steps;
+            Sequence::new(steps.clone(), |i, j| ordered(&steps[i], vvs[i], &steps[j], vvs[j]))
+        };
+
+        for q in self.path.schedule(path_id).asleep() {
+            // The next step of a thread is the same until it takes it
+            let next = events.iter()
+                .map(|event| event.step)
+                .find(|step| step.thread == q.as_usize())
+                .unwrap_or_else(|| step(q, self.threads[q].operation));
+
+            if sequence.starts_with(&next) {
+                return;
+            }
+        }
+
+        self.path.insert_wakeup(path_id, &sequence);
+    }
+
+    /// Compute the sleep set after taking the branch point `path_id`.
+    ///
+    /// Threads that were already explored from the branch point fall asleep.
+    /// A sleeping thread wakes up once the scheduled thread's operation is
+    /// dependent on the sleeping thread's next operation.
+    fn update_sleep_set(&mut self, path_id: usize) {
+        for th_id in self.path.schedule(path_id).visited() {
+            self.threads[th_id].sleeping = true;
+        }
+
+        let active_id = self.threads.active_id();
+        let operation = self.threads.active().operation;
+
+        for (th_id, th) in self.threads.iter_mut() {
+            if th_id == active_id {
+                th.sleeping = false;
+                continue;
+            }
+
+            let independent = match (operation, th.operation) {
+                (Some(a), Some(b)) => !a.is_dependent(&b),
+                _ => false,
+            };
+
+            if !independent {
+                th.sleeping = false;
+            }
+        }
+    }
+
+    /// Hash the program state: the registered shared state, the state of each
+    /// thread and the history of each object.
+    fn hash_state(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for state in &self.states {
+            state.hash_state(&mut hasher);
+        }
+
+        for (th_id, th) in self.threads.iter() {
+            th_id.hash(&mut hasher);
+            th.hash_state(&mut hasher);
+        }
+
+        self.objects.hash_histories(&mut hasher);
+        self.time.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Checks for futures that are parked without any way of being notified.
+    ///
+    /// This happens when a future returns `NotReady` without arranging for its
+    /// task to be notified. It is reported separately from a deadlock.
+    ///
+    /// Task handles only count while another thread is live. Handles that
+    /// remain once all other threads have terminated are held by the parked
+    /// thread itself, which cannot notify while it is parked.
+    fn check_lost_wakeup(&self) {
+        for (id, th) in self.threads.iter() {
+            if !th.is_blocked() || !th.future_parked || th.notified {
+                continue;
+            }
+
+            let live = self.threads.iter()
+                .any(|(other, th)| other != id && !th.is_terminated());
+
+            if live && th.task_refs > 0 {
+                continue;
+            }
+
+            let task = if th.tasks.is_empty() {
+                format!("the future on thread {}", id.as_usize())
+            } else {
+                let parked: Vec<_> = th.tasks.iter()
+                    .enumerate()
+                    .filter(|&(_, notified)| *notified == Some(false))
+                    .map(|(i, _)| i.to_string())
+                    .collect();
+
+                format!("task {} of the executor on thread {}", parked.join(", "), id.as_usize())
+            };
+
+            let polled = match th.last_polled {
+                Some(object) => format!("object {}", object.as_usize()),
+                None => "no object".to_string(),
+            };
+
+            panic!(
+                "lost wakeup: {} is parked, but no other live thread holds \
+                 its task handle; last polled {}",
+                task, polled);
+        }
+    }
+
     pub fn set_critical(&mut self) {
         self.threads.active_mut().critical = true;
+        self.timeline_begin(|| "critical".to_string());
     }
 
     pub fn unset_critical(&mut self) {
         self.threads.active_mut().critical = false;
+        self.timeline_end(|| "critical".to_string());
     }
 
     /// Insert a point of sequential consistency
     pub fn seq_cst(&mut self) {
         self.threads.active_mut().causality.join(&self.seq_cst_causality);
         self.seq_cst_causality.join(&self.threads.active().causality);
+
+        if let Some(ref mut graph) = self.graph {
+            graph.seq_cst(self.threads.active_id());
+        }
+    }
+
+    /// Add an instant event on the active thread's track of the timeline
+    pub fn timeline_instant<F>(&mut self, name: F)
+    where
+        F: FnOnce() -> String,
+    {
+        let (thread, ts) = (self.threads.active_id(), self.path.pos());
+
+        if let Some(ref mut timeline) = self.timeline {
+            timeline.instant(thread, ts, name());
+        }
+    }
+
+    /// Begin a span on the active thread's track of the timeline
+    pub fn timeline_begin<F>(&mut self, name: F)
+    where
+        F: FnOnce() -> String,
+    {
+        let (thread, ts) = (self.threads.active_id(), self.path.pos());
+
+        if let Some(ref mut timeline) = self.timeline {
+            timeline.begin(thread, ts, name());
+        }
+    }
+
+    /// End the span begun with the same name
+    pub fn timeline_end<F>(&mut self, name: F)
+    where
+        F: FnOnce() -> String,
+    {
+        let (thread, ts) = (self.threads.active_id(), self.path.pos());
+
+        if let Some(ref mut timeline) = self.timeline {
+            timeline.end(thread, ts, name());
+        }
+    }
+
+    /// Add a node for an operation of the active thread to the happens-before
+    /// graph. Returns `None` when the graph is not tracked.
+    pub fn trace<F>(&mut self, label: F) -> Option<usize>
+    where
+        F: FnOnce() -> String,
+    {
+        let thread = self.threads.active_id();
+        self.graph.as_mut().map(|graph| graph.node(thread, label()))
+    }
+
+    /// Returns the last graph node of the active thread
+    pub fn last_trace(&self) -> Option<usize> {
+        let thread = self.threads.active_id();
+        self.graph.as_ref().and_then(|graph| graph.last(thread))
+    }
+
+    pub fn trace_edge(&mut self, from: Option<usize>, to: Option<usize>, edge: Edge) {
+        if let (Some(graph), Some(from), Some(to)) = (self.graph.as_mut(), from, to) {
+            graph.edge(from, to, edge);
+        }
+    }
+
+    /// Add an edge from `from` to the next graph node of `thread`
+    pub fn trace_edge_to_next(&mut self, from: Option<usize>, thread: thread::Id, edge: Edge) {
+        if let (Some(graph), Some(from)) = (self.graph.as_mut(), from) {
+            graph.edge_to_next(from, thread, edge);
+        }
+    }
+}
+
+/// The step `thread` takes when scheduled with its next `operation`
+fn step(thread: thread::Id, operation: Option<object::Operation>) -> Step {
+    Step {
+        thread: thread.as_usize(),
+        object: operation.map(|operation| operation.object_id().as_usize()),
+        load: operation.map(|operation| operation.is_load()).unwrap_or(false),
     }
 }
 
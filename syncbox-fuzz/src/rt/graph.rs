@@ -0,0 +1,212 @@
+//! Happens-before graph of a single execution, written in the DOT format.
+
+use rt::thread;
+
+use std::collections::BTreeMap;
+use std::io;
+
+#[derive(Debug)]
+pub struct Graph {
+    nodes: Vec<Node>,
+
+    /// Relations between each pair of connected nodes
+    edges: BTreeMap<(usize, usize), Vec<Edge>>,
+
+    /// Last node of each thread
+    last: Vec<Option<usize>>,
+
+    /// Edges into the next node of each thread
+    pending: Vec<Vec<(usize, Edge)>>,
+
+    /// Last sequentially consistent operation
+    last_seq_cst: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Node {
+    thread: usize,
+    label: String,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Edge {
+    ProgramOrder,
+
+    /// A load reads the value of a store
+    ReadsFrom,
+
+    /// An acquire synchronizes with a release
+    SynchronizesWith,
+
+    /// Total order of sequentially consistent operations
+    SeqCst,
+
+    /// A mutex unlock precedes the next lock
+    Mutex,
+
+    /// A condvar notification wakes a waiter
+    Condvar,
+
+    Spawn,
+
+    Join,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph {
+            nodes: vec![],
+            edges: BTreeMap::new(),
+            last: vec![],
+            pending: vec![],
+            last_seq_cst: None,
+        }
+    }
+
+    /// Add a node for the next operation of `thread`
+    pub fn node(&mut self, thread: thread::Id, label: String) -> usize {
+        let thread = thread.as_usize();
+        let id = self.nodes.len();
+
+        if self.last.len() <= thread {
+            self.last.resize(thread + 1, None);
+            self.pending.resize(thread + 1, vec![]);
+        }
+
+        self.nodes.push(Node { thread, label });
+
+        if let Some(prev) = self.last[thread] {
+            self.edge(prev, id, Edge::ProgramOrder);
+        }
+
+        for (from, edge) in ::std::mem::replace(&mut self.pending[thread], vec![]) {
+            self.edge(from, id, edge);
+        }
+
+        self.last[thread] = Some(id);
+        id
+    }
+
+    /// Returns the last node of `thread`
+    pub fn last(&self, thread: thread::Id) -> Option<usize> {
+        self.last.get(thread.as_usize()).and_then(|last| *last)
+    }
+
+    pub fn edge(&mut self, from: usize, to: usize, edge: Edge) {
+        if from == to {
+            return;
+        }
+
+        let kinds = self.edges.entry((from, to)).or_insert_with(Vec::new);
+
+        if !kinds.contains(&edge) {
+            kinds.push(edge);
+        }
+    }
+
+    /// Add an edge from `from` to the next node of `thread`
+    pub fn edge_to_next(&mut self, from: usize, thread: thread::Id, edge: Edge) {
+        let thread = thread.as_usize();
+
+        if self.pending.len() <= thread {
+            self.last.resize(thread + 1, None);
+            self.pending.resize(thread + 1, vec![]);
+        }
+
+        self.pending[thread].push((from, edge));
+    }
+
+    /// Order the last node of `thread` after the previous sequentially
+    /// consistent operation. Only edges between threads are kept.
+    pub fn seq_cst(&mut self, thread: thread::Id) {
+        let node = match self.last(thread) {
+            Some(node) => node,
+            None => return,
+        };
+
+        if let Some(prev) = self.last_seq_cst {
+            if self.nodes[prev].thread != self.nodes[node].thread {
+                self.edge(prev, node, Edge::SeqCst);
+            }
+        }
+
+        self.last_seq_cst = Some(node);
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+        self.last.clear();
+        self.pending.clear();
+        self.last_seq_cst = None;
+    }
+
+    /// Write the graph in the DOT format. Each thread is a cluster of nodes,
+    /// edges between the same nodes are merged.
+    pub fn write_dot<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "digraph execution {{")?;
+        writeln!(out, "    node [shape=box];")?;
+
+        for (thread, _) in self.last.iter().enumerate().filter(|&(_, last)| last.is_some()) {
+            writeln!(out, "    subgraph cluster_{} {{", thread)?;
+            writeln!(out, "        label = \"thread {}\";", thread)?;
+
+            for (id, node) in self.nodes.iter().enumerate() {
+                if node.thread == thread {
+                    writeln!(out, "        n{} [label={:?}];", id, node.label)?;
+                }
+            }
+
+            writeln!(out, "    }}")?;
+        }
+
+        for (&(from, to), kinds) in &self.edges {
+            if *kinds == [Edge::ProgramOrder] {
+                writeln!(out, "    n{} -> n{};", from, to)?;
+                continue;
+            }
+
+            let labels: Vec<_> = kinds.iter()
+                .filter(|&&edge| edge != Edge::ProgramOrder)
+                .map(|edge| edge.label())
+                .collect();
+
+            // The first relation that is not program order picks the style
+            let style = kinds.iter()
+                .find(|&&edge| edge != Edge::ProgramOrder)
+                .unwrap()
+                .style();
+
+            writeln!(out, "    n{} -> n{} [label=\"{}\", {}];", from, to, labels.join(", "), style)?;
+        }
+
+        writeln!(out, "}}")
+    }
+}
+
+impl Edge {
+    fn label(&self) -> &'static str {
+        match *self {
+            Edge::ProgramOrder => "po",
+            Edge::ReadsFrom => "rf",
+            Edge::SynchronizesWith => "sw",
+            Edge::SeqCst => "sc",
+            Edge::Mutex => "mutex",
+            Edge::Condvar => "condvar",
+            Edge::Spawn => "spawn",
+            Edge::Join => "join",
+        }
+    }
+
+    fn style(&self) -> &'static str {
+        match *self {
+            Edge::ProgramOrder => "style=solid",
+            Edge::ReadsFrom => "style=dashed, color=blue",
+            Edge::SynchronizesWith => "color=red",
+            Edge::SeqCst => "style=dotted, color=gray",
+            Edge::Mutex => "color=purple",
+            Edge::Condvar => "color=orange",
+            Edge::Spawn | Edge::Join => "color=darkgreen",
+        }
+    }
+}
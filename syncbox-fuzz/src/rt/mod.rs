@@ -1,21 +1,28 @@
 pub(crate) mod arena;
 mod execution;
 mod fn_box;
+pub(crate) mod graph;
 pub(crate) mod object;
 pub(crate) mod oneshot;
-mod path;
+pub(crate) mod path;
 mod scheduler;
 mod synchronize;
 pub(crate) mod thread;
+pub(crate) mod timeline;
 mod vv;
+mod waker;
+pub(crate) mod wakeup;
 
-use self::fn_box::FnBox;
+pub(crate) use self::fn_box::FnBox;
 pub(crate) use self::synchronize::Synchronize;
 pub(crate) use self::path::Path;
 pub(crate) use self::vv::VersionVec;
 
 pub(crate) use self::execution::Execution;
-pub(crate) use self::scheduler::Scheduler;
+pub(crate) use self::scheduler::{enter_custom, Scheduler};
+
+use std::mem::replace;
+use std::time::Duration;
 
 pub fn spawn<F>(f: F)
 where
@@ -35,6 +42,7 @@ where
 /// Marks the current thread as blocked
 pub fn park() {
     execution(|execution| {
+        execution.timeline_instant(|| "park".to_string());
         execution.threads.active_mut().set_blocked();
         execution.threads.active_mut().operation = None;
         execution.schedule()
@@ -43,6 +51,63 @@ pub fn park() {
     Scheduler::switch();
 }
 
+/// Marks the current thread as blocked until it is unparked or the virtual
+/// clock reaches `deadline`.
+///
+/// Whether the timeout fires before any other thread makes progress is an
+/// explored branch. Returns `true` if the thread timed out.
+pub fn park_timeout(deadline: Duration) -> bool {
+    let timed_out = execution(|execution| {
+        if deadline <= execution.time {
+            return true;
+        }
+
+        if 1 == execution.path.branch_write(path::Kind::Timeout, 0..2) {
+            execution.advance_time(deadline);
+            return true;
+        }
+
+        let thread = execution.threads.active_mut();
+        thread.set_blocked();
+        thread.deadline = Some(deadline);
+        thread.operation = None;
+
+        false
+    });
+
+    if timed_out {
+        return true;
+    }
+
+    execution(|execution| {
+        execution.schedule()
+    });
+
+    Scheduler::switch();
+
+    execution(|execution| {
+        replace(&mut execution.threads.active_mut().timed_out, false)
+    })
+}
+
+/// Marks the current thread as blocked until the future it drives is notified.
+pub fn park_future() {
+    execution(|execution| {
+        let thread = execution.threads.active_mut();
+
+        thread.last_polled = thread.operation
+            .map(|operation| operation.object_id());
+
+        thread.future_parked = true;
+    });
+
+    park();
+
+    execution(|execution| {
+        execution.threads.active_mut().future_parked = false;
+    });
+}
+
 /// Add an execution branch point.
 fn branch<F, R>(f: F) -> R
 where
@@ -105,7 +170,6 @@ where
 
 if_futures! {
     use _futures::Future;
-    use std::mem::replace;
 
     pub fn wait_future<F>(mut f: F)
     where
@@ -126,12 +190,43 @@ if_futures! {
             });
 
             if !notified {
-                park();
+                park_future();
             }
         }
     }
 }
 
+/// Drive a `std::future::Future` to completion on the current thread.
+pub fn block_on<F>(mut f: F) -> F::Output
+where
+    F: ::std::future::Future,
+{
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    let waker = waker::root(thread::Id::current());
+    let mut cx = Context::from_waker(&waker);
+
+    // The future is shadowed, so it is never moved after being pinned.
+    let mut f = unsafe { Pin::new_unchecked(&mut f) };
+
+    loop {
+        if let Poll::Ready(ret) = f.as_mut().poll(&mut cx) {
+            return ret;
+        }
+
+        let notified = execution(|execution| {
+            replace(
+                &mut execution.threads.active_mut().notified,
+                false)
+        });
+
+        if !notified {
+            park_future();
+        }
+    }
+}
+
 pub fn thread_done() {
     execution(|execution| {
         execution.threads.active_mut().set_terminated();
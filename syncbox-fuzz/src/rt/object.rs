@@ -1,11 +1,17 @@
 use rt::vv::VersionVec;
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
 #[derive(Debug)]
 pub struct Object {
     /// Object kind
     kind: Kind,
+
+    /// Digest of the modifications made to the object, used to identify
+    /// revisited program states.
+    history: u64,
 }
 
 #[derive(Debug)]
@@ -13,13 +19,13 @@ pub struct Set {
     objects: Vec<Object>,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Id {
     id: usize,
     _p: PhantomData<::std::rc::Rc<()>>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Hash)]
 pub struct Operation {
     object_id: Id,
     action: Action,
@@ -30,10 +36,11 @@ enum Kind {
     Atomic(Atomic),
     Mutex(Option<Access>),
     Condvar(Option<Access>),
+    Barrier(Option<Access>),
     Thread(Option<Access>),
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Hash)]
 enum Action {
     /// Atomic load
     Load,
@@ -60,20 +67,35 @@ pub struct Access {
 }
 
 impl Object {
+    fn new(kind: Kind) -> Object {
+        Object { kind, history: 0 }
+    }
+
     pub fn atomic() -> Object {
-        Object { kind: Kind::Atomic(Atomic::default()) }
+        Object::new(Kind::Atomic(Atomic::default()))
     }
 
     pub fn mutex() -> Object {
-        Object { kind: Kind::Mutex(None) }
+        Object::new(Kind::Mutex(None))
     }
 
     pub fn condvar() -> Object {
-        Object { kind: Kind::Condvar(None) }
+        Object::new(Kind::Condvar(None))
+    }
+
+    pub fn barrier() -> Object {
+        Object::new(Kind::Barrier(None))
     }
 
     pub fn thread() -> Object {
-        Object { kind: Kind::Thread(None) }
+        Object::new(Kind::Thread(None))
+    }
+
+    /// Fold a modification into the object's history
+    fn record<T: Hash>(&mut self, value: T) {
+        let mut hasher = DefaultHasher::new();
+        (self.history, value).hash(&mut hasher);
+        self.history = hasher.finish();
     }
 }
 
@@ -89,6 +111,10 @@ impl Set {
         Id::from_usize(id)
     }
 
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
     pub fn last_dependent_accesses<'a>(&'a self, operation: Operation)
         -> Box<Iterator<Item = &'a Access> + 'a>
     {
@@ -108,6 +134,7 @@ impl Set {
             }
             Kind::Mutex(ref obj) => Box::new(obj.iter()),
             Kind::Condvar(ref obj) => Box::new(obj.iter()),
+            Kind::Barrier(ref obj) => Box::new(obj.iter()),
             Kind::Thread(ref obj) => Box::new(obj.iter()),
         }
     }
@@ -115,7 +142,17 @@ impl Set {
     pub fn set_last_access(&mut self, operation: Operation, access: Access) {
         use self::Action::*;
 
-        match self.objects[operation.object_id.as_usize()].kind {
+        let object = &mut self.objects[operation.object_id.as_usize()];
+
+        // The values written to atomics are recorded by `record_write`. Other
+        // objects keep internal state that is not visible here, so every
+        // operation counts as a modification.
+        match object.kind {
+            Kind::Atomic(_) => {}
+            _ => object.record(()),
+        }
+
+        match object.kind {
             Kind::Atomic(ref mut obj) => {
                 match operation.action {
                     Load => obj.last_load = Some(access),
@@ -129,10 +166,23 @@ impl Set {
             }
             Kind::Mutex(ref mut obj) => *obj = Some(access),
             Kind::Condvar(ref mut obj) => *obj = Some(access),
+            Kind::Barrier(ref mut obj) => *obj = Some(access),
             Kind::Thread(ref mut obj) => *obj = Some(access),
         }
     }
 
+    /// Record a value written to the object
+    pub fn record_write<T: Hash>(&mut self, id: Id, value: T) {
+        self.objects[id.as_usize()].record(value);
+    }
+
+    /// Hash the modification history of all objects
+    pub fn hash_histories<H: Hasher>(&self, hasher: &mut H) {
+        for object in &self.objects {
+            object.history.hash(hasher);
+        }
+    }
+
     pub fn clear(&mut self) {
         self.objects.clear();
     }
@@ -233,6 +283,37 @@ impl Operation {
     pub fn object_id(&self) -> Id {
         self.object_id
     }
+
+    /// Name of the access for operations on atomics
+    pub fn atomic_access(&self) -> Option<&'static str> {
+        match self.action {
+            Action::Load => Some("load"),
+            Action::Store => Some("store"),
+            Action::Rmw => Some("rmw"),
+            Action::Opaque => None,
+        }
+    }
+
+    /// Returns `true` if the operation is an atomic load
+    pub fn is_load(&self) -> bool {
+        match self.action {
+            Action::Load => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the outcome depends on the order in which the two
+    /// operations are performed.
+    pub fn is_dependent(&self, other: &Operation) -> bool {
+        if self.object_id != other.object_id {
+            return false;
+        }
+
+        match (self.action, other.action) {
+            (Action::Load, Action::Load) => false,
+            _ => true,
+        }
+    }
 }
 
 impl Kind {
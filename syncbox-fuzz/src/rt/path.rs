@@ -1,6 +1,8 @@
 use rt::thread;
+use rt::wakeup::{Sequence, Wakeup};
 
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
 
 /// An execution path
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,11 +19,96 @@ pub struct Path {
     /// set of branches.
     pos: usize,
 
+    /// The operation that each branch point was taken for, if known. Branch
+    /// points of paths recorded without it are replayed unchecked.
+    #[serde(default)]
+    kinds: Vec<Option<Kind>>,
+
     /// Tracks threads to be scheduled
     schedules: Vec<Schedule>,
 
     /// Atomic writes
     writes: Vec<VecDeque<usize>>,
+
+    /// Restricts exploration to a deterministic subset of the tree
+    #[serde(default)]
+    shard: Option<Shard>,
+
+    /// Maximum number of preemptions explored by a single execution
+    #[serde(default)]
+    preemption_bound: Option<usize>,
+
+    /// Number of branch points created by the current execution
+    #[serde(skip)]
+    new_branches: usize,
+
+    /// Set once every runnable thread of the current execution is in the sleep
+    /// set. All continuations have already been explored, so no new branches
+    /// are explored for the rest of the execution.
+    #[serde(skip)]
+    redundant: bool,
+
+    /// Branch point at which the current execution reached a program state
+    /// that was already fully explored. No alternatives are explored from it
+    /// on.
+    #[serde(skip)]
+    explored_from: Option<usize>,
+
+    /// Hashes of program states from which all executions have been explored
+    #[serde(skip)]
+    explored: HashSet<u64>,
+
+    /// Choices to make at new branch points, when valid. `None` entries and
+    /// branch points past the end take the default choice.
+    #[serde(skip)]
+    guide: Vec<Option<Choice>>,
+}
+
+/// The operation a branch point is taken for. A test that takes different
+/// branch points when replaying a path is not deterministic.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Kind {
+    /// Picking the thread to run next
+    Schedule,
+
+    /// Picking the write read by an atomic load on the object
+    Load(usize),
+
+    /// Whether a timed park times out
+    Timeout,
+
+    /// Picking the task polled next by an executor
+    Poll,
+
+    /// Picking the leader released by the barrier object
+    Leader(usize),
+}
+
+/// A choice made at a branch point
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Choice {
+    /// Index of the scheduled thread
+    Schedule(usize),
+
+    /// Index of the write read by a load
+    Write(usize),
+}
+
+/// Identifies the portion of the execution tree explored by a path.
+///
+/// The tree is split on the first `depth` branch points. Each distinct
+/// sequence of choices at those branch points is owned by exactly one shard.
+/// In order for the split to be deterministic, all alternatives at those
+/// branch points are explored instead of relying on DPOR to discover them.
+///
+/// Executions are run at least once by every shard until their owner is
+/// known, so executions with fewer than `depth` branch points are explored by
+/// all shards.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+    pub depth: usize,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -33,9 +120,24 @@ enum Branch {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Schedule {
     pub threads: Vec<Thread>,
+
+    /// The thread that was running at the branch point, if it could have kept
+    /// running. Scheduling any other thread is a preemption.
+    #[serde(default)]
+    current: Option<usize>,
+
+    /// Hash of the program state at the branch point, if states are cached
+    #[serde(skip)]
+    state: Option<u64>,
+
+    /// Step sequences left to explore from the branch point with optimal
+    /// DPOR. The first step of each root is the thread to schedule, the rest
+    /// guide the branch points that follow.
+    #[serde(default)]
+    wakeup: Vec<Wakeup>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Thread {
     /// The thread is currently disabled
     Disabled,
@@ -43,6 +145,10 @@ pub enum Thread {
     /// The thread should not be explored
     Skip,
 
+    /// The thread is in the sleep set. Its next operation was already explored
+    /// from an equivalent state, so it is not explored as a backtrack point.
+    Sleep,
+
     /// The thread is waiting to be explored
     Pending,
 
@@ -59,15 +165,172 @@ impl Path {
         Path {
             branches: vec![],
             pos: 0,
+            kinds: vec![],
             schedules: vec![],
             writes: vec![],
+            shard: None,
+            preemption_bound: None,
+            new_branches: 0,
+            redundant: false,
+            explored_from: None,
+            explored: HashSet::new(),
+            guide: vec![],
         }
     }
 
+    /// A path that makes the `guide` choices where they are possible. It is
+    /// used to replay a modified execution, which may diverge from the one it
+    /// was derived from.
+    pub fn guided(guide: Vec<Option<Choice>>) -> Path {
+        let mut path = Path::new();
+        path.guide = guide;
+        path
+    }
+
+    /// The choice made at each branch point
+    pub fn choices(&self) -> Vec<Option<Choice>> {
+        self.branches.iter()
+            .map(|branch| {
+                match *branch {
+                    Branch::Schedule(i) => {
+                        self.schedules[i].threads.iter()
+                            .position(Thread::is_active)
+                            .map(Choice::Schedule)
+                    }
+                    Branch::Write(i) => Some(Choice::Write(self.writes[i][0])),
+                }
+            })
+            .collect()
+    }
+
+    /// The simpler choice at branch point `index`, if any: not preempting the
+    /// running thread, or reading the latest write. `None` stands for the
+    /// default choice.
+    ///
+    /// Write alternatives are only known for paths that have not been
+    /// stepped.
+    pub fn simpler_choice(&self, index: usize) -> Option<Option<Choice>> {
+        match self.branches[index] {
+            Branch::Schedule(i) => {
+                let schedule = &self.schedules[i];
+
+                if schedule.is_preemption() {
+                    schedule.current.map(|current| Some(Choice::Schedule(current)))
+                } else {
+                    None
+                }
+            }
+            Branch::Write(_) => {
+                if self.is_stale(index) {
+                    Some(None)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Number of preemptions and of reads of stale writes, then the number of
+    /// branch points. Used to compare failing executions.
+    pub fn complexity(&self) -> (usize, usize, usize) {
+        let stale = (0..self.branches.len())
+            .filter(|&index| self.is_stale(index))
+            .count();
+
+        (self.preemptions(self.branches.len()), stale, self.branches.len())
+    }
+
+    /// Returns `true` if the branch point `index` is an atomic load that does
+    /// not read the latest write, which is the largest index. Other write
+    /// branch points, such as picking a barrier leader, have no simpler
+    /// choice.
+    fn is_stale(&self, index: usize) -> bool {
+        match (self.branches[index], self.kinds.get(index)) {
+            (Branch::Write(i), Some(&Some(Kind::Load(_)))) => {
+                let writes = &self.writes[i];
+                writes.iter().any(|&w| w > writes[0])
+            }
+            _ => false,
+        }
+    }
+
+    /// Only explore the part of the tree owned by the given shard
+    pub fn set_shard(&mut self, shard: Shard) {
+        assert!(shard.index < shard.count, "invalid shard {:?}", shard);
+        self.shard = Some(shard);
+    }
+
+    /// Only explore executions with at most `bound` preemptions
+    pub fn set_preemption_bound(&mut self, bound: Option<usize>) {
+        self.preemption_bound = bound;
+    }
+
     pub fn pos(&self) -> usize {
         self.pos
     }
 
+    /// Start the next execution from the first branch point.
+    ///
+    /// This is used to replay the path taken by a recorded execution.
+    pub fn rewind(&mut self) {
+        self.pos = 0;
+        self.new_branches = 0;
+        self.redundant = false;
+        self.explored_from = None;
+    }
+
+    pub fn is_redundant(&self) -> bool {
+        self.redundant
+    }
+
+    /// Stop exploring new branches for the rest of the current execution
+    pub fn set_redundant(&mut self) {
+        self.redundant = true;
+    }
+
+    /// Stop exploring alternatives from the branch point `index` on, which
+    /// reached an explored program state. Races with earlier branch points are
+    /// still backtracked.
+    pub fn set_explored_from(&mut self, index: usize) {
+        self.explored_from = Some(index);
+    }
+
+    /// Returns `true` if no new alternatives are explored for the rest of the
+    /// current execution
+    pub fn is_pruned(&self) -> bool {
+        self.redundant || self.explored_from.is_some()
+    }
+
+    /// Returns `true` if the next branch point has not been taken before
+    pub fn is_new_branch(&self) -> bool {
+        self.pos == self.branches.len()
+    }
+
+    /// Returns `true` if all executions from `state` have been explored
+    pub fn is_explored(&self, state: u64) -> bool {
+        self.explored.contains(&state)
+    }
+
+    /// Set the program state at the schedule branch point `index`. Once all
+    /// threads have been explored from the branch point, the state is
+    /// considered explored.
+    pub fn set_state(&mut self, index: usize, state: u64) {
+        self.schedule_mut(index).state = Some(state);
+    }
+
+    /// Kinds of the branch points created by the current execution
+    pub fn new_branches<'a>(&'a self) -> impl Iterator<Item = Kind> + 'a {
+        let start = self.branches.len() - self.new_branches;
+        self.kinds.iter().skip(start).filter_map(|kind| *kind)
+    }
+
+    pub fn schedule(&self, index: usize) -> &Schedule {
+        match self.branches[index] {
+            Branch::Schedule(val) => &self.schedules[val],
+            _ => panic!(),
+        }
+    }
+
     pub fn schedule_mut(&mut self, index: usize) -> &mut Schedule {
         match self.branches[index] {
             Branch::Schedule(val) => &mut self.schedules[val],
@@ -75,24 +338,87 @@ impl Path {
         }
     }
 
-    /// Returns the atomic write to read
-    pub fn branch_write<I>(&mut self, seed: I) -> usize
+    /// Explore scheduling `thread_id` at the schedule branch point `index`,
+    /// unless doing so exceeds the preemption bound or the branch point comes
+    /// after reaching an explored program state.
+    pub fn backtrack(&mut self, index: usize, thread_id: thread::Id) {
+        if self.is_explored_from(index) {
+            return;
+        }
+
+        let budget = self.preemption_bound.map(|bound| {
+            bound.saturating_sub(self.preemptions(index))
+        });
+
+        self.schedule_mut(index).backtrack(thread_id, budget);
+    }
+
+    /// Optimal-DPOR backtracking at the schedule branch point `index`: explore
+    /// the steps of `sequence` from it, unless the wakeup tree already leads
+    /// to an equivalent execution.
+    pub fn insert_wakeup(&mut self, index: usize, sequence: &Sequence) {
+        if self.is_explored_from(index) {
+            return;
+        }
+
+        let budget = self.preemption_bound.map(|bound| {
+            bound.saturating_sub(self.preemptions(index))
+        });
+
+        self.schedule_mut(index).insert_wakeup(sequence, budget);
+    }
+
+    /// Returns `true` if the branch point `index` comes after reaching an
+    /// explored program state
+    fn is_explored_from(&self, index: usize) -> bool {
+        self.explored_from.map(|from| index >= from).unwrap_or(false)
+    }
+
+    /// Number of preemptions taken by the branch points before `index`
+    fn preemptions(&self, index: usize) -> usize {
+        self.branches[..index].iter()
+            .filter(|branch| {
+                match **branch {
+                    Branch::Schedule(i) => self.schedules[i].is_preemption(),
+                    Branch::Write(_) => false,
+                }
+            })
+            .count()
+    }
+
+    /// Returns the write to read, or generally the index of the seed to pick
+    pub fn branch_write<I>(&mut self, kind: Kind, seed: I) -> usize
     where
         I: Iterator<Item = usize>
     {
         use self::Branch::Write;
 
+        self.check_kind(kind);
+
         if self.pos == self.branches.len() {
             let i = self.writes.len();
 
-            self.writes.push(seed.collect());
+            let mut writes: VecDeque<_> = if self.is_pruned() {
+                seed.take(1).collect()
+            } else {
+                seed.collect()
+            };
+
+            if let Some(&Some(Choice::Write(write))) = self.guide.get(self.pos) {
+                if let Some(j) = writes.iter().position(|&w| w == write) {
+                    writes.remove(j);
+                    writes.push_front(write);
+                }
+            }
 
-            self.branches.push(Branch::Write(i));
+            self.writes.push(writes);
+
+            self.push_branch(Branch::Write(i), kind);
         }
 
         let i = match self.branches[self.pos] {
             Write(i) => i,
-            _ => panic!("path entry {} is not a write", self.pos),
+            _ => panic!("non-deterministic test: path entry {} is not a write", self.pos),
         };
 
         self.pos += 1;
@@ -101,23 +427,101 @@ impl Path {
     }
 
     /// Returns the thread identifier to schedule
-    pub fn branch_thread<I>(&mut self, seed: I) -> Option<thread::Id>
+    ///
+    /// `current` is the thread that was running, if it is able to keep running.
+    pub fn branch_thread<I>(&mut self, current: Option<thread::Id>, seed: I) -> Option<thread::Id>
     where
         I: Iterator<Item = Thread>
     {
+        self.check_kind(Kind::Schedule);
+
         if self.pos == self.branches.len() {
             let i = self.schedules.len();
 
+            // Branch points that the shards are split on are fully explored,
+            // within the preemption bound.
+            let explore_all = match self.shard {
+                Some(shard) => self.pos < shard.depth,
+                None => false,
+            };
+
+            let budget = self.preemption_bound.map(|bound| {
+                bound.saturating_sub(self.preemptions(self.pos))
+            });
+
+            let allowed = |i: usize| {
+                budget != Some(0) || current.map(thread::Id::as_usize) == Some(i)
+            };
+
+            let mut threads: Vec<_> = seed.enumerate().map(|(i, th)| {
+                match th {
+                    Thread::Skip if explore_all && allowed(i) => Thread::Pending,
+                    th => th,
+                }
+            }).collect();
+
+            // Follow the wakeup sequences being explored. Steps of threads
+            // that are not able to run are dropped along with the rest of
+            // their sequences.
+            let wakeup: Vec<_> = self.next_wakeup()
+                .into_iter()
+                .filter(|node| {
+                    let th = node.step.thread;
+
+                    match threads.get(th) {
+                        Some(&Thread::Disabled) | Some(&Thread::Sleep) | None => false,
+                        _ => allowed(th),
+                    }
+                })
+                .collect();
+
+            for (i, node) in wakeup.iter().enumerate() {
+                let th = node.step.thread;
+
+                if i == 0 {
+                    for th in &mut threads {
+                        if th.is_active() {
+                            *th = Thread::Skip;
+                        }
+                    }
+
+                    threads[th] = Thread::Active;
+                } else {
+                    threads[th] = Thread::Pending;
+                }
+            }
+
+            if let Some(&Some(Choice::Schedule(th))) = self.guide.get(self.pos) {
+                let enabled = threads.get(th)
+                    .map(|th| !th.is_disabled())
+                    .unwrap_or(false);
+
+                if enabled {
+                    for th in &mut threads {
+                        if th.is_active() {
+                            *th = Thread::Skip;
+                        }
+                    }
+
+                    threads[th] = Thread::Active;
+                }
+            }
+
             self.schedules.push(Schedule {
-                threads: seed.collect(),
+                threads,
+                current: current.map(thread::Id::as_usize),
+                state: None,
+                wakeup,
             });
 
-            self.branches.push(Branch::Schedule(i));
+            self.push_branch(Branch::Schedule(i), Kind::Schedule);
+        } else {
+            self.check_runnable(seed);
         }
 
         let i = match self.branches[self.pos] {
             Branch::Schedule(i) => i,
-            _ => panic!(),
+            _ => panic!("non-deterministic test: path entry {} is not a schedule", self.pos),
         };
 
         self.pos += 1;
@@ -130,31 +534,318 @@ impl Path {
             .map(|(i, _)| thread::Id::from_usize(i))
     }
 
+    /// The wakeup subtree to follow from a new schedule branch point: the
+    /// children of the step taken at the previous one
+    fn next_wakeup(&self) -> Vec<Wakeup> {
+        let prev = self.branches.iter()
+            .rev()
+            .filter_map(|branch| match *branch {
+                Branch::Schedule(i) => Some(&self.schedules[i]),
+                Branch::Write(_) => None,
+            })
+            .next();
+
+        prev.and_then(|schedule| schedule.active_wakeup())
+            .map(|node| node.children.clone())
+            .unwrap_or_default()
+    }
+
+    fn push_branch(&mut self, branch: Branch, kind: Kind) {
+        // Branch points loaded without their kind stay unknown
+        let len = self.branches.len();
+        self.kinds.resize(len, None);
+        self.kinds.push(Some(kind));
+
+        self.branches.push(branch);
+        self.new_branches += 1;
+    }
+
+    /// Panics if the branch point at the current position was recorded for a
+    /// different kind of operation, with the recorded and replayed branch
+    /// points.
+    fn check_kind(&self, kind: Kind) {
+        let expected = match self.kinds.get(self.pos) {
+            Some(&Some(expected)) => expected,
+            _ => return,
+        };
+
+        if expected != kind {
+            self.diverged(&expected.to_string(), &kind.to_string());
+        }
+    }
+
+    /// Panics if the thread recorded at the current schedule branch point is
+    /// not able to run. `seed` is the state of each thread.
+    fn check_runnable<I>(&self, mut seed: I)
+    where
+        I: Iterator<Item = Thread>
+    {
+        let active = match self.branches[self.pos] {
+            Branch::Schedule(i) => self.schedules[i].threads.iter().position(Thread::is_active),
+            Branch::Write(_) => None,
+        };
+
+        if let Some(active) = active {
+            let runnable = seed.nth(active)
+                .map(|th| !th.is_disabled())
+                .unwrap_or(false);
+
+            if !runnable {
+                self.diverged(
+                    &format!("schedule of thread {}", active),
+                    &format!("thread {} not runnable", active));
+            }
+        }
+    }
+
+    fn diverged(&self, expected: &str, got: &str) -> ! {
+        let trace = |kinds: &[Option<Kind>]| {
+            kinds.iter()
+                .enumerate()
+                .map(|(i, kind)| match *kind {
+                    Some(kind) => format!("  {}: {}\n", i, kind),
+                    None => format!("  {}: unknown\n", i),
+                })
+                .collect::<String>()
+        };
+
+        panic!(
+            "non-deterministic test: expected {} at step {}, got {}\n\
+             recorded:\n{}replayed:\n{}  {}: {}\n",
+            expected, self.pos, got,
+            trace(&self.kinds),
+            trace(self.kinds.get(..self.pos).unwrap_or(&self.kinds)), self.pos, got);
+    }
+
+    /// Estimate the fraction of the execution tree that has been explored.
+    ///
+    /// The path must be positioned on the next execution to run. Subtrees of a
+    /// branch point are assumed to be of equal size. Already explored writes
+    /// are no longer tracked, so write branch points only account for the
+    /// remaining writes.
+    pub fn coverage(&self) -> f64 {
+        use self::Branch::*;
+
+        let mut remaining = 0.0;
+        let mut weight = 1.0;
+
+        for branch in &self.branches {
+            let (pending, total) = match *branch {
+                Schedule(i) => {
+                    let threads = &self.schedules[i].threads;
+
+                    let pending = threads.iter()
+                        .filter(|th| th.is_pending())
+                        .count();
+
+                    let visited = threads.iter()
+                        .filter(|th| th.is_visited())
+                        .count();
+
+                    (pending, pending + visited + 1)
+                }
+                Write(i) => (self.writes[i].len() - 1, self.writes[i].len()),
+            };
+
+            weight /= total as f64;
+            remaining += weight * pending as f64;
+        }
+
+        // The next execution has not run yet
+        remaining += weight;
+
+        1.0 - remaining
+    }
+
+    /// Split off part of the unexplored search tree.
+    ///
+    /// One of the pending alternatives at the shallowest branch point that has
+    /// any is removed from `self` and returned as a new path. The two paths
+    /// then explore disjoint subtrees. Returns `None` if there is nothing left
+    /// to split off.
+    pub fn split(&mut self) -> Option<Path> {
+        use self::Branch::*;
+
+        let at = self.branches.iter().position(|branch| {
+            match *branch {
+                Schedule(i) => {
+                    self.schedules[i].threads.iter().any(Thread::is_pending)
+                }
+                Write(i) => self.writes[i].len() > 1,
+            }
+        })?;
+
+        let mut split = Path::new();
+        split.shard = self.shard;
+        split.preemption_bound = self.preemption_bound;
+        split.kinds = self.kinds.get(..at + 1).unwrap_or(&self.kinds).to_vec();
+
+        for pos in 0..at {
+            match self.branches[pos] {
+                Schedule(i) => {
+                    // Pending threads remain with `self`
+                    let threads = self.schedules[i].threads.iter()
+                        .map(|th| match *th {
+                            Thread::Pending => Thread::Visited,
+                            th => th,
+                        })
+                        .collect();
+
+                    split.branches.push(Schedule(split.schedules.len()));
+                    split.schedules.push(self::Schedule {
+                        threads,
+                        current: self.schedules[i].current,
+                        state: None,
+                        wakeup: self.schedules[i].active_wakeup().cloned().into_iter().collect(),
+                    });
+                }
+                Write(i) => {
+                    let mut writes = VecDeque::new();
+                    writes.push_back(self.writes[i][0]);
+
+                    split.branches.push(Write(split.writes.len()));
+                    split.writes.push(writes);
+                }
+            }
+        }
+
+        match self.branches[at] {
+            Schedule(i) => {
+                let schedule = &mut self.schedules[i];
+                let stolen = schedule.next_pending().unwrap();
+
+                // The stolen thread's wakeup sequences go with it
+                let wakeup = schedule.wakeup.iter()
+                    .position(|node| node.step.thread == stolen)
+                    .map(|j| schedule.wakeup.remove(j));
+
+                let threads = &mut schedule.threads;
+
+                let split_threads = threads.iter()
+                    .enumerate()
+                    .map(|(j, th)| match *th {
+                        _ if j == stolen => Thread::Active,
+                        Thread::Active | Thread::Pending => Thread::Visited,
+                        th => th,
+                    })
+                    .collect();
+
+                // The stolen thread is now explored by the split path
+                threads[stolen] = Thread::Visited;
+
+                split.branches.push(Schedule(split.schedules.len()));
+                split.schedules.push(self::Schedule {
+                    threads: split_threads,
+                    current: self.schedules[i].current,
+                    state: None,
+                    wakeup: wakeup.into_iter().collect(),
+                });
+            }
+            Write(i) => {
+                let mut writes = VecDeque::new();
+                writes.push_back(self.writes[i].pop_back().unwrap());
+
+                split.branches.push(Write(split.writes.len()));
+                split.writes.push(writes);
+            }
+        }
+
+        Some(split)
+    }
+
     /// Returns `false` if there are no more paths to explore
     pub fn step(&mut self) -> bool {
+        loop {
+            if !self.step_branch() {
+                return false;
+            }
+
+            let shard = match self.shard {
+                Some(shard) => shard,
+                None => return true,
+            };
+
+            // The owner of the next execution is not yet known.
+            if self.branches.len() < shard.depth {
+                return true;
+            }
+
+            if self.shard_key(shard.depth) % shard.count == shard.index {
+                return true;
+            }
+
+            // Skip the subtree, it is explored by another shard.
+            self.truncate(shard.depth);
+        }
+    }
+
+    /// Hash of the choices made at the first `depth` branch points
+    fn shard_key(&self, depth: usize) -> usize {
+        use self::Branch::*;
+
+        self.branches[..depth].iter().fold(0usize, |key, branch| {
+            let choice = match *branch {
+                Schedule(i) => {
+                    self.schedules[i].threads.iter()
+                        .position(Thread::is_active)
+                        .unwrap()
+                }
+                Write(i) => self.writes[i][0],
+            };
+
+            key.wrapping_mul(31).wrapping_add(choice + 1)
+        })
+    }
+
+    fn truncate(&mut self, len: usize) {
+        use self::Branch::*;
+
+        while self.branches.len() > len {
+            match self.branches.pop().unwrap() {
+                Schedule(_) => {
+                    self.schedules.pop();
+                }
+                Write(_) => {
+                    self.writes.pop();
+                }
+            }
+        }
+
+        self.kinds.truncate(len);
+    }
+
+    fn step_branch(&mut self) -> bool {
         use self::Branch::*;
 
         self.pos = 0;
+        self.new_branches = 0;
+        self.redundant = false;
+        self.explored_from = None;
 
         while self.branches.len() > 0 {
             match self.branches.last().unwrap() {
                 &Schedule(i) => {
-                    // Transition the active thread to visited
-                    self.schedules[i].threads.iter_mut()
-                        .find(|th| th.is_active())
-                        .map(|th| {
-                            *th = Thread::Visited
-                        });
+                    let schedule = &mut self.schedules[i];
+
+                    // Transition the active thread to visited, along with its
+                    // wakeup sequences
+                    if let Some(active) = schedule.threads.iter().position(Thread::is_active) {
+                        schedule.threads[active] = Thread::Visited;
+                        schedule.wakeup.retain(|node| node.step.thread != active);
+                    }
 
                     // Find a pending thread and transition it to active
-                    let rem = self.schedules[i].threads.iter_mut()
-                        .find(|th| th.is_pending())
+                    let rem = schedule.next_pending()
                         .map(|th| {
-                            *th = Thread::Active;
+                            schedule.threads[th] = Thread::Active;
                         })
                         .is_some();
 
                     if !rem {
+                        if let Some(state) = self.schedules[i].state {
+                            self.explored.insert(state);
+                        }
+
                         self.branches.pop();
                         self.schedules.pop();
                         continue;
@@ -171,25 +862,100 @@ impl Path {
                 }
             }
 
+            self.kinds.truncate(self.branches.len());
             return true;
         }
 
+        self.kinds.clear();
         false
     }
 }
 
+impl fmt::Display for Kind {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Kind::Schedule => write!(fmt, "schedule"),
+            Kind::Load(object) => write!(fmt, "atomic load on object {}", object),
+            Kind::Timeout => write!(fmt, "park timeout"),
+            Kind::Poll => write!(fmt, "task poll"),
+            Kind::Leader(object) => write!(fmt, "barrier leader on object {}", object),
+        }
+    }
+}
+
 impl Schedule {
-    pub fn backtrack(&mut self, thread_id: thread::Id) {
+    /// `budget` is the number of preemptions that may still be taken at this
+    /// branch point, if bounded.
+    fn backtrack(&mut self, thread_id: thread::Id, budget: Option<usize>) {
         let thread_id = thread_id.as_usize();
+        let current = self.current;
+
+        let allowed = |i: usize| budget != Some(0) || current == Some(i);
 
         if self.threads[thread_id].is_enabled() {
-            self.threads[thread_id].explore();
+            if allowed(thread_id) {
+                self.threads[thread_id].explore();
+            }
         } else {
-            for th in &mut self.threads {
-                th.explore();
+            for (i, th) in self.threads.iter_mut().enumerate() {
+                if allowed(i) {
+                    th.explore();
+                }
             }
         }
     }
+
+    /// Threads that have already been explored from this branch point
+    pub fn visited<'a>(&'a self) -> impl Iterator<Item = thread::Id> + 'a {
+        self.threads.iter()
+            .enumerate()
+            .filter(|&(_, th)| th.is_visited())
+            .map(|(i, _)| thread::Id::from_usize(i))
+    }
+
+    /// Threads in the sleep set of the branch point, including the ones that
+    /// have already been explored from it
+    pub fn asleep<'a>(&'a self) -> impl Iterator<Item = thread::Id> + 'a {
+        self.threads.iter()
+            .enumerate()
+            .filter(|&(_, th)| th.is_visited() || th.is_sleep())
+            .map(|(i, _)| thread::Id::from_usize(i))
+    }
+
+    fn insert_wakeup(&mut self, sequence: &Sequence, budget: Option<usize>) {
+        let current = self.current;
+        let threads = &mut self.threads;
+
+        let allowed = |i: usize| {
+            threads[i].is_skip() && (budget != Some(0) || current == Some(i))
+        };
+
+        if let Some(i) = sequence.insert(&mut self.wakeup, allowed) {
+            threads[i].explore();
+        }
+    }
+
+    /// The wakeup tree node of the thread scheduled at the branch point
+    fn active_wakeup(&self) -> Option<&Wakeup> {
+        let active = self.threads.iter().position(Thread::is_active)?;
+        self.wakeup.iter().find(|node| node.step.thread == active)
+    }
+
+    /// The next thread to explore. Wakeup sequences are explored in the order
+    /// they were inserted, before the other pending threads.
+    fn next_pending(&self) -> Option<usize> {
+        self.wakeup.iter()
+            .map(|node| node.step.thread)
+            .find(|&i| self.threads[i].is_pending())
+            .or_else(|| self.threads.iter().position(Thread::is_pending))
+    }
+
+    fn is_preemption(&self) -> bool {
+        match self.current {
+            Some(current) => !self.threads[current].is_active(),
+            None => false,
+        }
+    }
 }
 
 impl Thread {
@@ -202,6 +968,27 @@ impl Thread {
         }
     }
 
+    fn is_disabled(&self) -> bool {
+        match *self {
+            Thread::Disabled => true,
+            _ => false,
+        }
+    }
+
+    fn is_skip(&self) -> bool {
+        match *self {
+            Thread::Skip => true,
+            _ => false,
+        }
+    }
+
+    fn is_sleep(&self) -> bool {
+        match *self {
+            Thread::Sleep => true,
+            _ => false,
+        }
+    }
+
     fn is_pending(&self) -> bool {
         match *self {
             Thread::Pending => true,
@@ -216,6 +1003,13 @@ impl Thread {
         }
     }
 
+    fn is_visited(&self) -> bool {
+        match *self {
+            Thread::Visited => true,
+            _ => false,
+        }
+    }
+
     fn is_enabled(&self) -> bool {
         !self.is_pending()
     }
@@ -0,0 +1,82 @@
+//! Dispatches to a user provided `scheduler::Scheduler`.
+
+use rt::{Execution, FnBox};
+use scheduler::{self, Task};
+
+use std::cell::Cell;
+use std::fmt;
+
+pub struct Scheduler {
+    inner: Box<scheduler::Scheduler>,
+}
+
+// Runtime running the model threads on this OS thread. Set by `Task::run`.
+thread_local!(static CURRENT: Cell<Option<*const scheduler::Scheduler>> = Cell::new(None));
+
+impl Scheduler {
+    pub fn new(inner: Box<scheduler::Scheduler>) -> Scheduler {
+        Scheduler { inner }
+    }
+
+    /// Access the execution
+    pub fn with_execution<F, R>(f: F) -> R
+    where
+        F: FnOnce(&mut Execution) -> R,
+    {
+        let mut f = Some(f);
+        let mut ret = None;
+
+        with_current(|current| {
+            current.with_execution(&mut |execution| {
+                let f = f.take().expect("`with_execution` called the function twice");
+                ret = Some(f(unsafe { execution.get() }));
+            });
+        });
+
+        ret.expect("`with_execution` did not call the function")
+    }
+
+    /// Perform a context switch
+    pub fn switch() {
+        with_current(|current| current.switch());
+    }
+
+    pub fn spawn(f: Box<FnBox>) {
+        with_current(|current| current.spawn(Task::new(f, current)));
+    }
+
+    pub fn run<F>(&mut self, execution: &mut Execution, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let inner = &*self.inner as *const scheduler::Scheduler;
+
+        // The runtime may run thread 0 on the current OS thread
+        enter(inner);
+
+        self.inner.run(scheduler::Execution::new(execution), Task::new(Box::new(f), inner));
+    }
+}
+
+/// Make `scheduler` the runtime of model threads run on this OS thread
+pub fn enter(scheduler: *const scheduler::Scheduler) {
+    super::set_custom();
+    CURRENT.with(|cell| cell.set(Some(scheduler)));
+}
+
+fn with_current<F, R>(f: F) -> R
+where
+    F: FnOnce(&(scheduler::Scheduler + 'static)) -> R,
+{
+    let current = CURRENT.with(|cell| cell.get())
+        .expect("model thread not run by a custom runtime");
+
+    f(unsafe { &*current })
+}
+
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Scheduler")
+            .finish()
+    }
+}
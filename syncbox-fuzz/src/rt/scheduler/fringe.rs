@@ -1,4 +1,5 @@
 use rt::{thread, Execution, FnBox};
+use super::overflow;
 
 use fringe::{
     Generator,
@@ -15,6 +16,15 @@ pub struct Scheduler {
     /// Threads
     threads: Vec<Thread>,
 
+    /// Approximate top of each thread's stack
+    stack_tops: Vec<usize>,
+
+    /// Size of each thread's stack, in bytes
+    stack_size: usize,
+
+    /// Number of threads to keep around
+    capacity: usize,
+
     next_thread: usize,
 
     queued_spawn: VecDeque<Box<FnBox>>,
@@ -33,15 +43,18 @@ scoped_mut_thread_local! {
 
 thread_local!(static YIELDER: Cell<*const Yielder<Option<Box<FnBox>>, ()>> = Cell::new(ptr::null()));
 
-const STACK_SIZE: usize = 1 << 23;
-
 impl Scheduler {
     /// Create an execution
-    pub fn new(capacity: usize) -> Scheduler {
-        let threads = spawn_threads(capacity);
+    pub fn new(capacity: usize, stack_size: usize) -> Scheduler {
+        overflow::install();
+
+        let (threads, stack_tops) = spawn_threads(capacity, stack_size);
 
         Scheduler {
             threads,
+            stack_tops,
+            stack_size,
+            capacity,
             next_thread: 0,
             queued_spawn: VecDeque::new(),
         }
@@ -74,6 +87,24 @@ impl Scheduler {
         // Set the scheduler kind
         super::set_fringe();
 
+        // Replace the threads dropped by an execution that panicked
+        if self.threads.len() < self.capacity {
+            let missing = self.capacity - self.threads.len();
+            let (threads, stack_tops) = spawn_threads(missing, self.stack_size);
+
+            self.threads.extend(threads);
+            self.stack_tops.extend(stack_tops);
+        }
+
+        // The threads started by the execution cannot be resumed if it panics
+        let reset = ResetOnPanic(self);
+        reset.0.run_threads(execution, f);
+    }
+
+    fn run_threads<F>(&mut self, execution: &mut Execution, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
         self.next_thread = 1;
         self.threads[0].resume(Some(Box::new(f)));
 
@@ -102,9 +133,13 @@ impl Scheduler {
         };
 
         let threads = &mut self.threads;
+        let top = self.stack_tops[thread.as_usize()];
+        let stack_size = self.stack_size;
 
         STATE.set(unsafe { transmute_lt(&mut state) }, || {
-            threads[thread.as_usize()].resume(None);
+            overflow::run(thread.as_usize(), top, stack_size, || {
+                threads[thread.as_usize()].resume(None);
+            });
         });
     }
 }
@@ -130,11 +165,29 @@ impl fmt::Debug for Scheduler {
     }
 }
 
-fn spawn_threads(n: usize) -> Vec<Thread> {
+/// Drops the threads started by an execution that panicked, along with the
+/// threads it spawned. They are replaced on the next run.
+struct ResetOnPanic<'a>(&'a mut Scheduler);
+
+impl<'a> Drop for ResetOnPanic<'a> {
+    fn drop(&mut self) {
+        if ::std::thread::panicking() {
+            let scheduler = &mut *self.0;
+
+            scheduler.threads.drain(..scheduler.next_thread);
+            scheduler.stack_tops.drain(..scheduler.next_thread);
+            scheduler.queued_spawn.clear();
+        }
+    }
+}
+
+fn spawn_threads(n: usize, stack_size: usize) -> (Vec<Thread>, Vec<usize>) {
     (0..n).map(|_| {
-        let stack = OsStack::new(STACK_SIZE).unwrap();
+        let stack = OsStack::new(stack_size).unwrap();
 
         let mut g: Thread = Generator::new(stack, move |yielder, _| {
+            overflow::mark_top();
+
             struct UnsetTls;
 
             impl Drop for UnsetTls {
@@ -156,8 +209,8 @@ fn spawn_threads(n: usize) -> Vec<Thread> {
             }
         });
         g.resume(None);
-        g
-    }).collect()
+        (g, overflow::marked_top())
+    }).unzip()
 }
 
 unsafe fn transmute_lt<'a, 'b>(state: &'a mut State<'b>) -> &'a mut State<'static> {
@@ -1,14 +1,25 @@
 use rt::{thread, Execution, FnBox};
+use super::overflow;
 
 use generator::{self, Gn, Generator};
 
 use std::collections::VecDeque;
 use std::fmt;
+use std::mem;
 
 pub struct Scheduler {
     /// Threads
     threads: Vec<Thread>,
 
+    /// Approximate top of each thread's stack
+    stack_tops: Vec<usize>,
+
+    /// Size of each thread's stack, in bytes
+    stack_size: usize,
+
+    /// Number of threads to keep around
+    capacity: usize,
+
     next_thread: usize,
 
     queued_spawn: VecDeque<Box<FnBox>>,
@@ -25,15 +36,18 @@ struct State<'a> {
     queued_spawn: &'a mut VecDeque<Box<FnBox>>,
 }
 
-const STACK_SIZE: usize = 1 << 23;
-
 impl Scheduler {
     /// Create an execution
-    pub fn new(capacity: usize) -> Scheduler {
-        let threads = spawn_threads(capacity);
+    pub fn new(capacity: usize, stack_size: usize) -> Scheduler {
+        overflow::install();
+
+        let (threads, stack_tops) = spawn_threads(capacity, stack_size);
 
         Scheduler {
             threads,
+            stack_tops,
+            stack_size,
+            capacity,
             next_thread: 0,
             queued_spawn: VecDeque::new(),
         }
@@ -66,6 +80,24 @@ impl Scheduler {
         // Set the scheduler kind
         super::set_generator();
 
+        // Replace the threads dropped by an execution that panicked
+        if self.threads.len() < self.capacity {
+            let missing = self.capacity - self.threads.len();
+            let (threads, stack_tops) = spawn_threads(missing, self.stack_size);
+
+            self.threads.extend(threads);
+            self.stack_tops.extend(stack_tops);
+        }
+
+        // The threads started by the execution cannot be resumed if it panics
+        let reset = ResetOnPanic(self);
+        reset.0.run_threads(execution, f);
+    }
+
+    fn run_threads<F>(&mut self, execution: &mut Execution, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
         self.next_thread = 1;
         self.threads[0].set_para(Some(Box::new(f)));
         self.threads[0].resume();
@@ -96,9 +128,13 @@ impl Scheduler {
         };
 
         let threads = &mut self.threads;
+        let top = self.stack_tops[thread.as_usize()];
+        let stack_size = self.stack_size;
 
         STATE.set(unsafe { transmute_lt(&mut state) }, || {
-            threads[thread.as_usize()].resume();
+            overflow::run(thread.as_usize(), top, stack_size, || {
+                threads[thread.as_usize()].resume();
+            });
         });
     }
 }
@@ -111,9 +147,31 @@ impl fmt::Debug for Scheduler {
     }
 }
 
-fn spawn_threads(n: usize) -> Vec<Thread> {
+/// Drops the threads started by an execution that panicked, along with the
+/// threads it spawned. They are replaced on the next run.
+struct ResetOnPanic<'a>(&'a mut Scheduler);
+
+impl<'a> Drop for ResetOnPanic<'a> {
+    fn drop(&mut self) {
+        if ::std::thread::panicking() {
+            let scheduler = &mut *self.0;
+
+            scheduler.threads.drain(..scheduler.next_thread);
+            scheduler.stack_tops.drain(..scheduler.next_thread);
+            scheduler.queued_spawn.clear();
+        }
+    }
+}
+
+fn spawn_threads(n: usize, stack_size: usize) -> (Vec<Thread>, Vec<usize>) {
+    // The generator stack size is in words. Odd sizes make the generator
+    // crate fill the whole stack on creation.
+    let words = (stack_size / mem::size_of::<usize>()) & !1;
+
     (0..n).map(|_| {
-        let mut g = Gn::new_opt(STACK_SIZE, move || {
+        let mut g = Gn::new_opt(words, move || {
+            overflow::mark_top();
+
             loop {
                 let f: Option<Box<FnBox>> = generator::yield_(()).unwrap();
                 generator::yield_with(());
@@ -123,8 +181,8 @@ fn spawn_threads(n: usize) -> Vec<Thread> {
             // done!();
         });
         g.resume();
-        g
-    }).collect()
+        (g, overflow::marked_top())
+    }).unzip()
 }
 
 unsafe fn transmute_lt<'a, 'b>(state: &'a mut State<'b>) -> &'a mut State<'static> {
@@ -1,7 +1,11 @@
+mod custom;
 #[cfg(feature = "fringe")]
 mod fringe;
 mod gen;
+mod overflow;
 mod std;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+mod ucontext;
 
 #[cfg(not(feature = "fringe"))]
 mod fringe {
@@ -36,20 +40,62 @@ mod fringe {
     }
 }
 
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+mod ucontext {
+    //! `Runtime::Ucontext` does not exist on other targets, so the scheduler
+    //! is never created.
+
+    use rt::{Execution, FnBox};
+
+    #[derive(Debug)]
+    pub struct Scheduler;
+
+    impl Scheduler {
+        /// Access the execution
+        pub fn with_execution<F, R>(_: F) -> R
+        where
+            F: FnOnce(&mut Execution) -> R,
+        {
+            unreachable!("ucontext runtime is only available on linux-gnu");
+        }
+
+        pub fn switch() {
+            unreachable!("ucontext runtime is only available on linux-gnu");
+        }
+
+        pub fn spawn(_: Box<FnBox>) {
+            unreachable!("ucontext runtime is only available on linux-gnu");
+        }
+
+        pub fn run<F>(&mut self, _: &mut Execution, _: F)
+        where
+            F: FnOnce() + Send + 'static,
+        {
+            unreachable!("ucontext runtime is only available on linux-gnu");
+        }
+    }
+}
+
 use rt::{Execution, FnBox};
+use scheduler;
 use std::cell::Cell;
 
+pub(crate) use self::custom::enter as enter_custom;
+
 #[derive(Debug)]
 pub struct Scheduler {
-    kind: Kind<gen::Scheduler, std::Scheduler, fringe::Scheduler>,
+    kind: Kind<gen::Scheduler, std::Scheduler, fringe::Scheduler, ucontext::Scheduler, custom::Scheduler>,
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Kind<T = (), U = (), V = ()> {
+enum Kind<T = (), U = (), V = (), W = (), X = ()> {
     Generator(T),
     Thread(U),
     #[allow(dead_code)]
     Fringe(V),
+    #[allow(dead_code)]
+    Ucontext(W),
+    Custom(X),
 }
 
 use self::Kind::*;
@@ -58,10 +104,10 @@ thread_local!(static KIND: Cell<Kind> = Cell::new(Generator(())));
 
 impl Scheduler {
     /// Create a generator based scheduler
-    pub fn new_generator(capacity: usize) -> Scheduler {
+    pub fn new_generator(capacity: usize, stack_size: usize) -> Scheduler {
         assert!(capacity > 0);
         Scheduler {
-            kind: Generator(gen::Scheduler::new(capacity)),
+            kind: Generator(gen::Scheduler::new(capacity, stack_size)),
         }
     }
 
@@ -74,10 +120,26 @@ impl Scheduler {
     }
 
     #[cfg(feature = "fringe")]
-    pub fn new_fringe(capacity: usize) -> Scheduler {
+    pub fn new_fringe(capacity: usize, stack_size: usize) -> Scheduler {
         assert!(capacity > 0);
         Scheduler {
-            kind: Fringe(fringe::Scheduler::new(capacity)),
+            kind: Fringe(fringe::Scheduler::new(capacity, stack_size)),
+        }
+    }
+
+    /// Create a scheduler switching between threads with `swapcontext`
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn new_ucontext(capacity: usize, stack_size: usize) -> Scheduler {
+        assert!(capacity > 0);
+        Scheduler {
+            kind: Ucontext(ucontext::Scheduler::new(capacity, stack_size)),
+        }
+    }
+
+    /// Create a scheduler running threads with a user provided runtime
+    pub fn new_custom(inner: Box<scheduler::Scheduler>) -> Scheduler {
+        Scheduler {
+            kind: Custom(custom::Scheduler::new(inner)),
         }
     }
 
@@ -90,6 +152,8 @@ impl Scheduler {
             Thread(_) => std::Scheduler::with_execution(f),
             Generator(_) => gen::Scheduler::with_execution(f),
             Fringe(_) => fringe::Scheduler::with_execution(f),
+            Ucontext(_) => ucontext::Scheduler::with_execution(f),
+            Custom(_) => custom::Scheduler::with_execution(f),
         }
     }
 
@@ -99,6 +163,8 @@ impl Scheduler {
             Thread(_) => std::Scheduler::switch(),
             Generator(_) => gen::Scheduler::switch(),
             Fringe(_) => fringe::Scheduler::switch(),
+            Ucontext(_) => ucontext::Scheduler::switch(),
+            Custom(_) => custom::Scheduler::switch(),
         }
     }
 
@@ -107,6 +173,8 @@ impl Scheduler {
             Thread(_) => std::Scheduler::spawn(f),
             Generator(_) => gen::Scheduler::spawn(f),
             Fringe(_) => fringe::Scheduler::spawn(f),
+            Ucontext(_) => ucontext::Scheduler::spawn(f),
+            Custom(_) => custom::Scheduler::spawn(f),
         }
     }
 
@@ -118,6 +186,8 @@ impl Scheduler {
             Thread(ref mut v) => v.run(execution, f),
             Generator(ref mut v) => v.run(execution, f),
             Fringe(ref mut v) => v.run(execution, f),
+            Ucontext(ref mut v) => v.run(execution, f),
+            Custom(ref mut v) => v.run(execution, f),
         }
     }
 }
@@ -134,3 +204,12 @@ fn set_generator() {
 fn set_fringe() {
     KIND.with(|c| c.set(Fringe(())))
 }
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn set_ucontext() {
+    KIND.with(|c| c.set(Ucontext(())))
+}
+
+fn set_custom() {
+    KIND.with(|c| c.set(Custom(())))
+}
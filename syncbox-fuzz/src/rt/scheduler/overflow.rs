@@ -0,0 +1,213 @@
+//! Stack overflow reporting for model threads running on coroutine stacks.
+//!
+//! Coroutine stacks are followed by a `PROT_NONE` guard page, so overflowing
+//! one faults instead of corrupting memory. A fault handler checks whether the
+//! faulting address is within the stack of the running model thread and, if
+//! so, reports which model thread overflowed before aborting. Other faults are
+//! passed on to the previously installed handler.
+
+use libc;
+
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::mem;
+use std::ptr;
+use std::sync::Once;
+
+/// Stack of the model thread currently running on this OS thread
+#[derive(Debug, Clone, Copy)]
+struct Running {
+    thread: usize,
+    top: usize,
+    size: usize,
+}
+
+thread_local!(static RUNNING: Cell<Option<Running>> = Cell::new(None));
+
+thread_local!(static ALT_STACK: AltStack = AltStack::new());
+
+thread_local!(static MARKED_TOP: Cell<usize> = Cell::new(0));
+
+static INSTALL: Once = Once::new();
+
+static mut PREV_SEGV: Option<libc::sigaction> = None;
+static mut PREV_BUS: Option<libc::sigaction> = None;
+
+/// Size of the signal stack installed on OS threads that lack one
+const ALT_STACK_SIZE: usize = 1 << 16;
+
+type Handler = extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void);
+
+/// Signal stack mapped for an OS thread, unmapped when the thread exits
+struct AltStack(*mut libc::c_void);
+
+/// Install the fault handler. Only the first call has an effect.
+pub fn install() {
+    INSTALL.call_once(|| unsafe {
+        PREV_SEGV = Some(set_handler(libc::SIGSEGV));
+        PREV_BUS = Some(set_handler(libc::SIGBUS));
+    });
+}
+
+/// Record the approximate top of the current stack. Called first thing on a
+/// new coroutine stack.
+#[inline(never)]
+pub fn mark_top() {
+    let marker = 0u8;
+    MARKED_TOP.with(|cell| cell.set(&marker as *const u8 as usize));
+}
+
+/// Returns the stack top recorded by the last call to `mark_top`
+pub fn marked_top() -> usize {
+    MARKED_TOP.with(|cell| cell.get())
+}
+
+/// Run `f` as model thread `thread`, on the stack starting at `top`.
+pub fn run<F, R>(thread: usize, top: usize, size: usize, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    struct Reset(Option<Running>);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            RUNNING.with(|cell| cell.set(self.0));
+        }
+    }
+
+    // The fault handler runs on the signal stack, the coroutine stack is full.
+    ALT_STACK.with(|_| {});
+
+    let prev = RUNNING.with(|cell| cell.replace(Some(Running { thread, top, size })));
+    let _reset = Reset(prev);
+
+    f()
+}
+
+unsafe fn set_handler(signum: libc::c_int) -> libc::sigaction {
+    let mut action: libc::sigaction = mem::zeroed();
+    action.sa_sigaction = handler as Handler as libc::sighandler_t;
+    action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+    libc::sigemptyset(&mut action.sa_mask);
+
+    let mut prev: libc::sigaction = mem::zeroed();
+    libc::sigaction(signum, &action, &mut prev);
+    prev
+}
+
+extern "C" fn handler(signum: libc::c_int, info: *mut libc::siginfo_t, ctx: *mut libc::c_void) {
+    let addr = unsafe { (*info).si_addr() as usize };
+
+    let running = RUNNING.try_with(|cell| cell.get()).ok().and_then(|running| running);
+
+    if let Some(running) = running {
+        // The stack grows down towards the guard page. Allow for the stack
+        // top being approximate and for the guard page.
+        let bottom = running.top.saturating_sub(running.size + 2 * page_size());
+
+        if addr >= bottom && addr < running.top {
+            report(running);
+        }
+    }
+
+    unsafe {
+        let prev = if signum == libc::SIGSEGV { PREV_SEGV } else { PREV_BUS };
+        let prev = match prev {
+            Some(prev) => prev,
+            None => return,
+        };
+
+        if prev.sa_sigaction == libc::SIG_DFL || prev.sa_sigaction == libc::SIG_IGN {
+            // Returning re-executes the faulting instruction with the
+            // previous disposition.
+            libc::sigaction(signum, &prev, ptr::null_mut());
+        } else if prev.sa_flags & libc::SA_SIGINFO != 0 {
+            let f: Handler = mem::transmute(prev.sa_sigaction);
+            f(signum, info, ctx);
+        } else {
+            let f: extern "C" fn(libc::c_int) = mem::transmute(prev.sa_sigaction);
+            f(signum);
+        }
+    }
+}
+
+/// Print the overflow without allocating, then abort.
+fn report(running: Running) -> ! {
+    let mut buf = [0u8; 256];
+
+    let len = {
+        let mut out = io::Cursor::new(&mut buf[..]);
+
+        let _ = write!(
+            out,
+            "\nmodel thread stack overflow: thread {} overflowed its {} byte stack; \
+             increase `Builder::stack_size`\n",
+            running.thread,
+            running.size);
+
+        out.position() as usize
+    };
+
+    unsafe {
+        libc::write(2, buf.as_ptr() as *const libc::c_void, len);
+        libc::abort();
+    }
+}
+
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+impl AltStack {
+    fn new() -> AltStack {
+        unsafe {
+            let mut current: libc::stack_t = mem::zeroed();
+            libc::sigaltstack(ptr::null(), &mut current);
+
+            // Keep the signal stack the runtime set up, if any
+            if current.ss_flags & libc::SS_DISABLE == 0 {
+                return AltStack(ptr::null_mut());
+            }
+
+            let sp = libc::mmap(
+                ptr::null_mut(),
+                ALT_STACK_SIZE,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANON,
+                -1,
+                0);
+
+            if sp == libc::MAP_FAILED {
+                return AltStack(ptr::null_mut());
+            }
+
+            let stack = libc::stack_t {
+                ss_sp: sp,
+                ss_flags: 0,
+                ss_size: ALT_STACK_SIZE,
+            };
+
+            libc::sigaltstack(&stack, ptr::null_mut());
+            AltStack(sp)
+        }
+    }
+}
+
+impl Drop for AltStack {
+    fn drop(&mut self) {
+        if self.0.is_null() {
+            return;
+        }
+
+        unsafe {
+            let stack = libc::stack_t {
+                ss_sp: ptr::null_mut(),
+                ss_flags: libc::SS_DISABLE,
+                ss_size: ALT_STACK_SIZE,
+            };
+
+            libc::sigaltstack(&stack, ptr::null_mut());
+            libc::munmap(self.0, ALT_STACK_SIZE);
+        }
+    }
+}
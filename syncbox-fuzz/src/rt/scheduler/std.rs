@@ -1,20 +1,29 @@
-#![allow(warnings)]
-
 use rt::{Execution, FnBox};
-use rt::thread::Id as ThreadId;
-use std::collections::VecDeque;
+
+use std::any::Any;
+use std::cell::{Cell, UnsafeCell};
 use std::fmt;
-use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
-use std::sync::{Arc, Mutex, Condvar};
-use std::sync::atomic::AtomicUsize;
-use std::sync::atomic::Ordering::{Acquire, Release, Relaxed, SeqCst};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::atomic::Ordering::{Acquire, Release, Relaxed};
 use std::thread;
 
-#[derive(Debug)]
+/// Runs each model thread on its own OS thread.
+///
+/// Only the active model thread runs. A switch wakes the OS thread of the next
+/// active model thread, then waits to be woken in turn. The OS threads are
+/// kept around and reused across executions.
+///
+/// When an execution panics, the model threads still waiting for their turn
+/// are cancelled: each one unwinds in turn before `run` returns. A model thread
+/// that is unwinding does not switch to other model threads.
 pub struct Scheduler {
     shared: Arc<Shared>,
 
+    workers: Vec<thread::JoinHandle<()>>,
+
     // Not `Send`
     _p: ::std::marker::PhantomData<::std::rc::Rc<()>>,
 }
@@ -23,87 +32,84 @@ scoped_thread_local! {
     static STATE: State
 }
 
-#[derive(Debug)]
 struct Shared {
-    synced: Mutex<Synced>,
-    active_thread: AtomicUsize,
+    /// Execution being run. Only accessed by the active model thread.
+    execution: Cell<*mut Execution>,
+
+    /// One slot per model thread
+    threads: Vec<Slot>,
+
+    /// Index of the next model thread to spawn
     next_thread: AtomicUsize,
-    done: AtomicUsize,
-    threads: Vec<Mutex<Thread>>,
-    condvars: Vec<Condvar>,
-    notify: thread::Thread,
+
+    /// Set when a model thread running on a worker panicked
+    panic: UnsafeCell<Option<Box<Any + Send>>>,
+
+    /// Set when the scheduler is dropped
+    shutdown: AtomicBool,
+
+    /// Set while the model threads of a panicked execution are cancelled
+    cancel: AtomicBool,
+
+    /// Woken once a cancelled model thread finished unwinding
+    cancelled: Signal,
 }
 
-#[derive(Debug)]
-struct Synced {
-    execution: *mut Execution,
+struct Slot {
+    /// Wakes the OS thread backing the model thread
+    signal: Signal,
+
+    /// Function to run when the model thread is first scheduled
+    f: UnsafeCell<Option<Box<FnBox>>>,
+
+    /// Set while a worker runs the model thread
+    running: AtomicBool,
 }
 
-#[derive(Debug)]
+/// Panic payload unwinding a cancelled model thread
+struct Cancel;
+
 struct State<'a> {
-    shared: &'a Arc<Shared>,
+    shared: &'a Shared,
     id: usize,
 }
 
-enum Thread {
-    Idle,
-    Pending(Box<FnBox>),
-    Running,
-    Shutdown,
-}
-
-/// Box<FnBox> is not send, but execution will be coordinated by a global lock.
-unsafe impl Send for Thread {}
-
-/// Access to the execution is guarded by a lock
+/// The execution, functions and panics are handed between OS threads. Only
+/// one OS thread runs at a time, and handing off goes through a `Signal`.
 unsafe impl Send for Shared {}
 unsafe impl Sync for Shared {}
 
-impl fmt::Debug for Thread {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let state = match *self {
-            Idle => "Idle",
-            Pending(_) => "Pending",
-            Running => "Running",
-            Shutdown => "Shutdown",
-        };
-
-        write!(fmt, "Thread::{}", state)
-    }
-}
-
-use self::Thread::*;
-
 impl Scheduler {
     /// Create an execution
     pub fn new(capacity: usize) -> Scheduler {
         let threads = (0..capacity)
-            .map(|_| Mutex::new(Idle))
-            .collect();
-
-        let condvars = (0..capacity)
-            .map(|_| Condvar::new())
+            .map(|_| Slot {
+                signal: Signal::new(),
+                f: UnsafeCell::new(None),
+                running: AtomicBool::new(false),
+            })
             .collect();
 
         let shared = Arc::new(Shared {
-            synced: Mutex::new(Synced {
-                execution: ptr::null_mut(),
-            }),
-            active_thread: AtomicUsize::new(0),
+            execution: Cell::new(ptr::null_mut()),
+            threads,
             next_thread: AtomicUsize::new(1),
-            done: AtomicUsize::new(0),
-            threads: threads,
-            condvars: condvars,
-            notify: thread::current(),
+            panic: UnsafeCell::new(None),
+            shutdown: AtomicBool::new(false),
+            cancel: AtomicBool::new(false),
+            cancelled: Signal::new(),
         });
 
-        for i in (1..capacity) {
-            let shared = shared.clone();
-            spawn_worker(i, shared);
-        }
+        let workers = (1..capacity)
+            .map(|i| {
+                let shared = shared.clone();
+                thread::spawn(move || run_worker(i, &shared))
+            })
+            .collect();
 
         Scheduler {
             shared,
+            workers,
             _p: ::std::marker::PhantomData,
         }
     }
@@ -114,64 +120,37 @@ impl Scheduler {
         F: FnOnce(&mut Execution) -> R,
     {
         STATE.with(|state| {
-            let mut synced = state.shared.synced.lock().unwrap();
-            f(unsafe { &mut *synced.execution })
+            f(unsafe { &mut *state.shared.execution.get() })
         })
     }
 
     /// Perform a context switch
     pub fn switch() {
-        Scheduler::switch2(false);
-    }
-
-    fn switch2(release_lock: bool) {
         STATE.with(|state| {
-            let active_id = {
-                let mut e = state.shared.synced.lock().unwrap();
-
-                let execution = e.execution;
-
-                unsafe {
-                    if !(*execution).threads.is_active() {
-                        return;
-                    }
-
-                    (*execution).threads.active_id()
-                }
-            };
-
-            if state.id == active_id.as_usize() {
+            // Destructors run while unwinding must not resume other model
+            // threads.
+            if thread::panicking() {
                 return;
             }
 
-            // Notify the thread
-            state.shared.active_thread.store(active_id.as_usize(), Release);
-            drop(state.shared.threads[active_id.as_usize()].lock().unwrap());
-            state.shared.condvars[active_id.as_usize()].notify_one();
-
-            if release_lock {
-                return;
+            if state.hand_off() {
+                state.shared.threads[state.id].signal.wait();
+                state.check_cancel();
+                state.check_panic();
             }
-
-            state.acquire_lock();
         });
     }
 
     pub fn spawn(f: Box<FnBox>) {
         STATE.with(|state| {
-            let shared = state.shared.clone();
-            let i = shared.next_thread.fetch_add(1, Acquire);
+            let i = state.shared.next_thread.fetch_add(1, Relaxed);
             assert!(i < state.shared.threads.len());
 
-            let mut th = state.shared.threads[i].lock().unwrap();
-
-            match mem::replace(&mut *th, Pending(f)) {
-                Idle => {}
-                actual => panic!("unexpected state; actual={:?}", actual),
+            // The worker takes the function once the model thread is first
+            // scheduled.
+            unsafe {
+                *state.shared.threads[i].f.get() = Some(f);
             }
-
-            drop(th);
-            state.shared.condvars[i].notify_one();
         });
     }
 
@@ -179,103 +158,97 @@ impl Scheduler {
     where
         F: FnOnce() + Send + 'static,
     {
-        self.shared.active_thread.store(0, Relaxed);
         self.shared.next_thread.store(1, Relaxed);
-        self.shared.done.store(0, Relaxed);
 
         assert!(!execution.schedule());
 
         // Set the STD context
         super::set_thread();
 
-        struct Reset<'a>(&'a Shared);
+        self.shared.execution.set(execution as *mut _);
 
-        impl<'a> Drop for Reset<'a> {
-            fn drop(&mut self) {
-                // *self.0.execution.lock().unwrap() = ptr::null_mut();
-            }
-        }
-
-        let _r = Reset(&self.shared);
-
-        self.shared.synced.lock().unwrap().execution = execution as *mut _;
+        // Cancels the other model threads if the execution panics, while the
+        // execution is still around.
+        let _guard = CancelOnPanic(&self.shared);
 
-        run_thread(0, &self.shared, f);
+        // The root model thread runs on the current OS thread
+        let done = run_thread(0, &self.shared, f);
 
-        loop {
-            let done = self.shared.done.load(Acquire);
-            let spawned = self.shared.next_thread.load(Acquire);
-
-            if done+1 == spawned {
-                break;
-            }
+        // Once the root model thread terminated, its signal is woken when
+        // the execution finishes or panics.
+        if !done {
+            self.shared.threads[0].signal.wait();
+        }
 
-            thread::park();
+        if let Some(err) = unsafe { (*self.shared.panic.get()).take() } {
+            panic::resume_unwind(err);
         }
     }
 }
 
 impl Drop for Scheduler {
     fn drop(&mut self) {
-        // TODO: implement
+        // All workers are idle. Model threads of a panicked execution were
+        // cancelled by `run`.
+        self.shared.shutdown.store(true, Release);
+
+        for slot in &self.shared.threads[1..] {
+            slot.signal.wake();
+        }
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
     }
 }
 
-fn spawn_worker(i: usize, shared: Arc<Shared>) {
-    thread::spawn(move || {
-        super::set_thread();
-
-        run_worker(i, &shared);
-    });
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Scheduler")
+            .field("threads", &self.shared.threads.len())
+            .finish()
+    }
 }
 
-fn run_worker(i: usize, shared: &Arc<Shared>) {
+fn run_worker(i: usize, shared: &Shared) {
+    super::set_thread();
+
     loop {
-        // Get a fn
-        let f = {
-            let mut th = shared.threads[i].lock().unwrap();
-
-            loop {
-                match *th {
-                    Idle => {
-                        th = shared.condvars[i].wait(th).unwrap();
-                    }
-                    Pending(_) => {
-                        match mem::replace(&mut *th, Running) {
-                            Pending(f) => break f,
-                            _ => panic!("unexpected state"),
-                        }
-                    }
-                    Running => panic!("unexpected state"),
-                    Shutdown => return,
-                }
-            }
-        };
+        // Wait to be scheduled
+        shared.threads[i].signal.wait();
 
-        run_thread(i, shared, move || f.call());
+        if shared.shutdown.load(Acquire) {
+            return;
+        }
 
-        // Transition to idle
-        let mut th = shared.threads[i].lock().unwrap();
+        let f = unsafe { (*shared.threads[i].f.get()).take() }
+            .expect("model thread scheduled before being spawned");
 
-        loop {
-            match mem::replace(&mut *th, Idle) {
-                Running => break,
-                Shutdown => return,
-                s => panic!("unexpected state = {:?}", s),
-            }
-        }
+        shared.threads[i].running.store(true, Relaxed);
 
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_thread(i, shared, move || f.call())
+        }));
 
-        let prev = shared.done.fetch_add(1, Release);
-        let next_thread = shared.next_thread.load(Acquire);
+        shared.threads[i].running.store(false, Relaxed);
 
-        if prev + 2 == next_thread {
-            shared.notify.unpark();
+        match res {
+            Ok(true) => shared.threads[0].signal.wake(),
+            Ok(false) => {}
+            Err(ref err) if err.is::<Cancel>() => shared.cancelled.wake(),
+            Err(err) => {
+                // The root model thread is either waiting for the execution
+                // to finish or for its turn.
+                unsafe { *shared.panic.get() = Some(err) };
+                shared.threads[0].signal.wake();
+            }
         }
     }
 }
 
-fn run_thread<F>(id: usize, shared: &Arc<Shared>, f: F)
+/// Run model thread `id`, then hand off to the next active model thread.
+/// Returns `true` if all model threads have terminated.
+fn run_thread<F>(id: usize, shared: &Shared, f: F) -> bool
 where
     F: FnOnce()
 {
@@ -284,12 +257,43 @@ where
         id,
     };
 
-    state.acquire_lock();
-
     STATE.set(unsafe { transmute_lt(&state) }, || {
         f();
-        Scheduler::switch2(true);
-    });
+
+        // Once handed off, the next model thread may cancel this one
+        shared.threads[id].running.store(false, Relaxed);
+        !state.hand_off()
+    })
+}
+
+struct CancelOnPanic<'a>(&'a Shared);
+
+impl<'a> Drop for CancelOnPanic<'a> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.cancel();
+        }
+    }
+}
+
+impl Shared {
+    /// Unwind the model threads of a panicked execution, one at a time, and
+    /// drop the ones that never started. Leaves the workers ready for the
+    /// next execution.
+    fn cancel(&self) {
+        self.cancel.store(true, Release);
+
+        for slot in &self.threads[1..] {
+            unsafe { (*slot.f.get()).take() };
+
+            if slot.running.load(Relaxed) {
+                slot.signal.wake();
+                self.cancelled.wait();
+            }
+        }
+
+        self.cancel.store(false, Release);
+    }
 }
 
 unsafe fn transmute_lt<'a, 'b>(state: &'a State<'b>) -> &'a State<'static> {
@@ -297,12 +301,129 @@ unsafe fn transmute_lt<'a, 'b>(state: &'a State<'b>) -> &'a State<'static> {
 }
 
 impl<'a> State<'a> {
-    fn acquire_lock(&self) {
-        // Now, wait until we acquired the lock
-        let mut th = self.shared.threads[self.id].lock().unwrap();
+    /// Unwind this model thread if its execution panicked
+    fn check_cancel(&self) {
+        if self.shared.cancel.load(Acquire) {
+            panic::resume_unwind(Box::new(Cancel));
+        }
+    }
+
+    /// Resume a panic raised by a model thread running on a worker
+    fn check_panic(&self) {
+        if let Some(err) = unsafe { (*self.shared.panic.get()).take() } {
+            panic::resume_unwind(err);
+        }
+    }
+
+    /// Wake the OS thread of the active model thread, unless it is this one.
+    ///
+    /// Returns `false` if this thread remains active or no thread is active.
+    fn hand_off(&self) -> bool {
+        let execution = unsafe { &*self.shared.execution.get() };
+
+        if !execution.threads.is_active() {
+            return false;
+        }
+
+        let active_id = execution.threads.active_id().as_usize();
+
+        if active_id == self.id {
+            return false;
+        }
+
+        self.shared.threads[active_id].signal.wake();
+        true
+    }
+}
 
-        while self.id != self.shared.active_thread.load(Acquire) {
-            th = self.shared.condvars[self.id].wait(th).unwrap();
+cfg_if! {
+    if #[cfg(target_os = "linux")] {
+        use libc;
+        use std::sync::atomic::AtomicU32;
+
+        const EMPTY: u32 = 0;
+        const WOKEN: u32 = 1;
+        const SLEEPING: u32 = 2;
+
+        /// A wake-up token, waited on with a futex
+        struct Signal {
+            state: AtomicU32,
+        }
+
+        impl Signal {
+            fn new() -> Signal {
+                Signal { state: AtomicU32::new(EMPTY) }
+            }
+
+            /// Block until woken, consuming the wake-up
+            fn wait(&self) {
+                loop {
+                    if self.state.compare_exchange(WOKEN, EMPTY, Acquire, Relaxed).is_ok() {
+                        return;
+                    }
+
+                    // Let the waker know a syscall is needed. Fails if the
+                    // wake-up just arrived, in which case `FUTEX_WAIT`
+                    // returns immediately.
+                    let _ = self.state.compare_exchange(EMPTY, SLEEPING, Relaxed, Relaxed);
+
+                    unsafe {
+                        libc::syscall(
+                            libc::SYS_futex,
+                            &self.state as *const AtomicU32,
+                            libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                            SLEEPING,
+                            ptr::null::<libc::timespec>());
+                    }
+                }
+            }
+
+            fn wake(&self) {
+                if self.state.swap(WOKEN, Release) != SLEEPING {
+                    return;
+                }
+
+                unsafe {
+                    libc::syscall(
+                        libc::SYS_futex,
+                        &self.state as *const AtomicU32,
+                        libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+                        1u32);
+                }
+            }
+        }
+    } else {
+        use std::sync::{Condvar, Mutex};
+
+        /// A wake-up token
+        struct Signal {
+            woken: Mutex<bool>,
+            condvar: Condvar,
+        }
+
+        impl Signal {
+            fn new() -> Signal {
+                Signal {
+                    woken: Mutex::new(false),
+                    condvar: Condvar::new(),
+                }
+            }
+
+            /// Block until woken, consuming the wake-up
+            fn wait(&self) {
+                let mut woken = self.woken.lock().unwrap();
+
+                while !*woken {
+                    woken = self.condvar.wait(woken).unwrap();
+                }
+
+                *woken = false;
+            }
+
+            fn wake(&self) {
+                *self.woken.lock().unwrap() = true;
+                self.condvar.notify_one();
+            }
         }
     }
 }
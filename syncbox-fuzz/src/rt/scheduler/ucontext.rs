@@ -0,0 +1,322 @@
+//! Coroutines switched with `swapcontext`.
+
+use rt::{thread, Execution, FnBox};
+use super::overflow;
+
+use libc;
+
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+pub struct Scheduler {
+    /// Threads
+    threads: Vec<Box<Thread>>,
+
+    /// Approximate top of each thread's stack
+    stack_tops: Vec<usize>,
+
+    /// Size of each thread's stack, in bytes
+    stack_size: usize,
+
+    /// Number of threads to keep around
+    capacity: usize,
+
+    next_thread: usize,
+
+    queued_spawn: VecDeque<Box<FnBox>>,
+}
+
+/// A coroutine. Boxed, as the saved contexts must not move.
+struct Thread {
+    /// Context of the coroutine, saved when it suspends
+    context: libc::ucontext_t,
+
+    /// Context of the scheduler, saved when the coroutine is resumed
+    caller: libc::ucontext_t,
+
+    /// Function passed to the coroutine on resume
+    para: Option<Box<FnBox>>,
+
+    /// Set when the function run by the coroutine panicked
+    panic: Option<Box<Any + Send>>,
+
+    stack: Stack,
+}
+
+/// Coroutine stack, with a guard page below it
+struct Stack {
+    /// Start of the mapping, including the guard page
+    ptr: *mut libc::c_void,
+
+    /// Length of the mapping
+    len: usize,
+
+    /// Size of the guard page
+    guard: usize,
+}
+
+struct State<'a> {
+    execution: &'a mut Execution,
+    queued_spawn: &'a mut VecDeque<Box<FnBox>>,
+}
+
+scoped_mut_thread_local! {
+    static STATE: State
+}
+
+thread_local!(static RUNNING: Cell<*mut Thread> = Cell::new(ptr::null_mut()));
+
+impl Scheduler {
+    /// Create an execution
+    pub fn new(capacity: usize, stack_size: usize) -> Scheduler {
+        overflow::install();
+
+        let (threads, stack_tops) = spawn_threads(capacity, stack_size);
+
+        Scheduler {
+            threads,
+            stack_tops,
+            stack_size,
+            capacity,
+            next_thread: 0,
+            queued_spawn: VecDeque::new(),
+        }
+    }
+
+    /// Access the execution
+    pub fn with_execution<F, R>(f: F) -> R
+    where
+        F: FnOnce(&mut Execution) -> R,
+    {
+        STATE.with(|state| f(state.execution))
+    }
+
+    /// Perform a context switch
+    pub fn switch() {
+        assert!(suspend().is_none());
+    }
+
+    pub fn spawn(f: Box<FnBox>) {
+        STATE.with(|state| {
+            state.queued_spawn.push_back(f);
+        });
+    }
+
+    pub fn run<F>(&mut self, execution: &mut Execution, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+
+        // Set the scheduler kind
+        super::set_ucontext();
+
+        // Replace the threads dropped by an execution that panicked
+        if self.threads.len() < self.capacity {
+            let missing = self.capacity - self.threads.len();
+            let (threads, stack_tops) = spawn_threads(missing, self.stack_size);
+
+            self.threads.extend(threads);
+            self.stack_tops.extend(stack_tops);
+        }
+
+        // The threads started by the execution cannot be resumed if it panics
+        let reset = ResetOnPanic(self);
+        reset.0.run_threads(execution, f);
+    }
+
+    fn run_threads<F>(&mut self, execution: &mut Execution, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.next_thread = 1;
+        resume(&mut self.threads[0], Some(Box::new(f)));
+
+        loop {
+            if !execution.threads.is_active() {
+                return;
+            }
+
+            let active_thread = execution.threads.active_id();
+
+            self.tick(active_thread, execution);
+
+            while let Some(th) = self.queued_spawn.pop_front() {
+                let thread_id = self.next_thread;
+                self.next_thread += 1;
+
+                resume(&mut self.threads[thread_id], Some(th));
+            }
+        }
+    }
+
+    fn tick(&mut self, thread: thread::Id, execution: &mut Execution) {
+        let mut state = State {
+            execution: execution,
+            queued_spawn: &mut self.queued_spawn,
+        };
+
+        let threads = &mut self.threads;
+        let top = self.stack_tops[thread.as_usize()];
+        let stack_size = self.stack_size;
+
+        STATE.set(unsafe { transmute_lt(&mut state) }, || {
+            overflow::run(thread.as_usize(), top, stack_size, || {
+                resume(&mut threads[thread.as_usize()], None);
+            });
+        });
+    }
+}
+
+/// Switch to `thread`, passing it `para`. Returns once the thread suspends.
+///
+/// A panic in the thread is resumed on the caller.
+fn resume(thread: &mut Thread, para: Option<Box<FnBox>>) {
+    thread.para = para;
+
+    let prev = RUNNING.with(|cell| cell.replace(thread as *mut Thread));
+
+    let res = unsafe { libc::swapcontext(&mut thread.caller, &thread.context) };
+    assert_eq!(0, res, "swapcontext failed");
+
+    RUNNING.with(|cell| cell.set(prev));
+
+    if let Some(err) = thread.panic.take() {
+        panic::resume_unwind(err);
+    }
+}
+
+/// Switch back to the scheduler. Returns the function passed on resume.
+fn suspend() -> Option<Box<FnBox>> {
+    let thread = RUNNING.with(|cell| cell.get());
+    assert!(!thread.is_null());
+
+    unsafe {
+        let res = libc::swapcontext(&mut (*thread).context, &(*thread).caller);
+        assert_eq!(0, res, "swapcontext failed");
+
+        (*thread).para.take()
+    }
+}
+
+/// Entry point of every coroutine
+extern "C" fn entry() {
+    overflow::mark_top();
+
+    loop {
+        let f = suspend();
+        assert!(f.is_some());
+        Scheduler::switch();
+
+        // Unwinding out of the coroutine is not possible, the panic is
+        // resumed by the scheduler instead.
+        if let Err(err) = panic::catch_unwind(AssertUnwindSafe(|| f.unwrap().call())) {
+            let thread = RUNNING.with(|cell| cell.get());
+            unsafe { (*thread).panic = Some(err) };
+        }
+    }
+}
+
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Scheduler")
+            .finish()
+    }
+}
+
+/// Drops the threads started by an execution that panicked, along with the
+/// threads it spawned. They are replaced on the next run.
+struct ResetOnPanic<'a>(&'a mut Scheduler);
+
+impl<'a> Drop for ResetOnPanic<'a> {
+    fn drop(&mut self) {
+        if ::std::thread::panicking() {
+            let scheduler = &mut *self.0;
+
+            scheduler.threads.drain(..scheduler.next_thread);
+            scheduler.stack_tops.drain(..scheduler.next_thread);
+            scheduler.queued_spawn.clear();
+        }
+    }
+}
+
+fn spawn_threads(n: usize, stack_size: usize) -> (Vec<Box<Thread>>, Vec<usize>) {
+    (0..n).map(|_| {
+        let mut thread = Box::new(Thread {
+            context: unsafe { mem::zeroed() },
+            caller: unsafe { mem::zeroed() },
+            para: None,
+            panic: None,
+            stack: Stack::new(stack_size),
+        });
+
+        unsafe {
+            let res = libc::getcontext(&mut thread.context);
+            assert_eq!(0, res, "getcontext failed");
+
+            thread.context.uc_stack.ss_sp = thread.stack.base();
+            thread.context.uc_stack.ss_size = thread.stack.size();
+            thread.context.uc_link = ptr::null_mut();
+
+            libc::makecontext(&mut thread.context, entry, 0);
+        }
+
+        // Run the coroutine until it waits for a function
+        resume(&mut thread, None);
+
+        (thread, overflow::marked_top())
+    }).unzip()
+}
+
+impl Stack {
+    fn new(size: usize) -> Stack {
+        let guard = unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize };
+        let len = ((size + guard - 1) & !(guard - 1)) + guard;
+
+        unsafe {
+            let ptr = libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANON | libc::MAP_STACK,
+                -1,
+                0);
+
+            if ptr == libc::MAP_FAILED {
+                panic!("failed to map a {} byte stack", len);
+            }
+
+            // The stack grows down into the guard page
+            let res = libc::mprotect(ptr, guard, libc::PROT_NONE);
+            assert_eq!(0, res, "failed to protect the stack guard page");
+
+            Stack { ptr, len, guard }
+        }
+    }
+
+    /// Lowest usable address of the stack
+    fn base(&self) -> *mut libc::c_void {
+        (self.ptr as usize + self.guard) as *mut libc::c_void
+    }
+
+    /// Usable size of the stack
+    fn size(&self) -> usize {
+        self.len - self.guard
+    }
+}
+
+impl Drop for Stack {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+unsafe fn transmute_lt<'a, 'b>(state: &'a mut State<'b>) -> &'a mut State<'static> {
+    ::std::mem::transmute(state)
+}
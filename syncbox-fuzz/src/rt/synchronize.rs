@@ -1,19 +1,36 @@
-use rt::{Execution, VersionVec};
+use rt::{thread, Execution, VersionVec};
+use rt::graph::Edge;
 
 use std::sync::atomic::Ordering::{self, *};
 
 #[derive(Debug, Clone)]
 pub(crate) struct Synchronize {
     happens_before: VersionVec,
+
+    /// Latest release by each thread joined into `happens_before`. Only
+    /// tracked while the happens-before graph is recorded.
+    releases: Vec<Release>,
+}
+
+#[derive(Debug, Clone)]
+struct Release {
+    thread: thread::Id,
+
+    /// Version of the releasing thread
+    version: usize,
+
+    /// Graph node of the release
+    node: usize,
 }
 
 impl Synchronize {
-    pub fn new(max_threads: usize) -> Self {
+    pub fn new(execution: &Execution) -> Self {
         let happens_before =
-            VersionVec::new(max_threads);
+            VersionVec::new(execution.max_threads, &execution.arena);
 
         Synchronize {
             happens_before,
+            releases: vec![],
         }
     }
 
@@ -50,10 +67,36 @@ impl Synchronize {
     }
 
     fn sync_acq(&mut self, execution: &mut Execution) {
+        if let Some(node) = execution.last_trace() {
+            // Releases the thread already synchronized with are implied by
+            // earlier edges.
+            let synced: Vec<_> = {
+                let causality = &execution.threads.active().causality;
+
+                self.releases.iter()
+                    .filter(|release| causality[release.thread] < release.version)
+                    .map(|release| release.node)
+                    .collect()
+            };
+
+            for release in synced {
+                execution.trace_edge(Some(release), Some(node), Edge::SynchronizesWith);
+            }
+        }
+
         execution.threads.active_mut().causality.join(&self.happens_before);
     }
 
     fn sync_rel(&mut self, execution: &mut Execution) {
         self.happens_before.join(&execution.threads.active().causality);
+
+        if let Some(node) = execution.last_trace() {
+            let thread = execution.threads.active_id();
+            let version = execution.threads.active().causality[thread];
+
+            // An earlier release by the same thread precedes this one
+            self.releases.retain(|release| release.thread != thread);
+            self.releases.push(Release { thread, version, node });
+        }
     }
 }
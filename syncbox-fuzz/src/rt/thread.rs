@@ -1,8 +1,11 @@
-use rt::object::Operation;
+use rt::object::{self, Operation};
+use rt::arena::Arena;
 use rt::vv::VersionVec;
 
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct Thread {
@@ -21,8 +24,33 @@ pub struct Thread {
     /// Tracks DPOR relations
     pub dpor_vv: VersionVec,
 
-    /// Tracks a future's `Task::notify` flag
+    /// Tracks a future's `Task::notify` or `Waker::wake` flag
     pub notified: bool,
+
+    /// Tracks the `Task::notify` flag of each task driven by a modeled
+    /// executor on this thread. `None` once the task has completed.
+    pub tasks: Vec<Option<bool>>,
+
+    /// The executor task currently being polled
+    pub active_task: Option<usize>,
+
+    /// Number of live task handles that are able to notify this thread
+    pub task_refs: usize,
+
+    /// True when the thread is parked waiting for a future notification
+    pub future_parked: bool,
+
+    /// The object last operated on by the future driven by this thread
+    pub last_polled: Option<object::Id>,
+
+    /// Virtual time at which a blocked thread times out
+    pub deadline: Option<Duration>,
+
+    /// True when the thread was unblocked by reaching its deadline
+    pub timed_out: bool,
+
+    /// True when the thread is in the sleep set of the current branch point
+    pub sleeping: bool,
 }
 
 #[derive(Debug)]
@@ -41,7 +69,7 @@ pub struct Id {
     _p: PhantomData<::std::rc::Rc<()>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Hash)]
 pub enum State {
     Runnable,
     Blocked,
@@ -50,14 +78,22 @@ pub enum State {
 }
 
 impl Thread {
-    fn new(max_threads: usize) -> Thread {
+    fn new(max_threads: usize, arena: &Arena) -> Thread {
         Thread {
             state: State::Runnable,
             critical: false,
             operation: None,
-            causality: VersionVec::new(max_threads),
-            dpor_vv: VersionVec::new(max_threads),
+            causality: VersionVec::new(max_threads, arena),
+            dpor_vv: VersionVec::new(max_threads, arena),
             notified: false,
+            tasks: vec![],
+            active_task: None,
+            task_refs: 0,
+            future_parked: false,
+            last_polled: None,
+            deadline: None,
+            timed_out: false,
+            sleeping: false,
         }
     }
 
@@ -104,6 +140,18 @@ impl Thread {
     pub fn set_terminated(&mut self) {
         self.state = State::Terminated;
     }
+
+    /// Hash the parts of the thread's state that determine how it may be
+    /// scheduled next. Clocks are left out, they differ between executions
+    /// reaching the same state.
+    pub fn hash_state<H: Hasher>(&self, hasher: &mut H) {
+        self.state.hash(hasher);
+        self.operation.hash(hasher);
+        self.sleeping.hash(hasher);
+        self.deadline.hash(hasher);
+        self.notified.hash(hasher);
+        self.tasks.hash(hasher);
+    }
 }
 
 impl Set {
@@ -117,8 +165,8 @@ impl Set {
         }
     }
 
-    /// Create a new thread
-    pub fn new_thread(&mut self) -> Id {
+    /// Create a new thread, allocating its version vectors in `arena`
+    pub fn new_thread(&mut self, arena: &Arena) -> Id {
         assert!(self.threads.len() < self.threads.capacity());
 
         // Get the identifier for the thread about to be created
@@ -126,7 +174,7 @@ impl Set {
         let max_threads = self.threads.capacity();
 
         // Push the thread onto the stack
-        self.threads.push(Thread::new(max_threads));
+        self.threads.push(Thread::new(max_threads, arena));
 
         if self.active.is_none() {
             self.active = Some(id);
@@ -139,6 +187,10 @@ impl Set {
         self.active.is_some()
     }
 
+    pub fn len(&self) -> usize {
+        self.threads.len()
+    }
+
     pub fn active_id(&self) -> Id {
         Id::from_usize(self.active.unwrap())
     }
@@ -245,10 +297,39 @@ impl Id {
         });
     }
 
-    #[cfg(feature = "futures")]
     pub fn future_notify(self) {
         super::execution(|execution| {
-            execution.threads.active_mut().notified = true;
+            execution.threads[self].notified = true;
+            execution.unpark_thread(self);
+        });
+    }
+
+    /// Track a new task handle referencing this thread
+    pub fn task_ref(self) {
+        super::execution(|execution| {
+            execution.threads[self].task_refs += 1;
+        });
+    }
+
+    /// Release a task handle referencing this thread
+    pub fn task_unref(self) {
+        // Don't touch the execution while unwinding from a failed check.
+        if ::std::thread::panicking() {
+            return;
+        }
+
+        super::execution(|execution| {
+            execution.threads[self].task_refs -= 1;
+        });
+    }
+
+    pub fn task_notify(self, task: usize) {
+        super::execution(|execution| {
+            // The executor may have completed already
+            if let Some(&mut Some(ref mut notified)) = execution.threads[self].tasks.get_mut(task) {
+                *notified = true;
+            }
+
             execution.unpark_thread(self);
         });
     }
@@ -0,0 +1,119 @@
+//! Timeline of a single execution, written as Chrome trace-event JSON.
+//!
+//! Each model thread is a track. Timestamps are branch positions, so one
+//! microsecond in the trace viewer is one branch point of the execution.
+
+use rt::thread;
+
+use serde_json;
+
+use std::io;
+
+#[derive(Debug)]
+pub struct Timeline {
+    events: Vec<Event>,
+
+    /// Number of threads seen, each gets a named track
+    threads: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Event {
+    name: String,
+
+    /// Event type: `B` and `E` delimit a span, `i` is an instant
+    ph: &'static str,
+
+    ts: usize,
+
+    pid: usize,
+
+    tid: usize,
+
+    /// Scope of instant events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Metadata {
+    name: &'static str,
+    ph: &'static str,
+    pid: usize,
+    tid: usize,
+    args: ThreadName,
+}
+
+#[derive(Debug, Serialize)]
+struct ThreadName {
+    name: String,
+}
+
+#[derive(Serialize)]
+struct Trace {
+    #[serde(rename = "traceEvents")]
+    trace_events: Vec<serde_json::Value>,
+}
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline {
+            events: vec![],
+            threads: 0,
+        }
+    }
+
+    pub fn instant(&mut self, thread: thread::Id, ts: usize, name: String) {
+        self.push(thread, ts, name, "i");
+    }
+
+    pub fn begin(&mut self, thread: thread::Id, ts: usize, name: String) {
+        self.push(thread, ts, name, "B");
+    }
+
+    pub fn end(&mut self, thread: thread::Id, ts: usize, name: String) {
+        self.push(thread, ts, name, "E");
+    }
+
+    fn push(&mut self, thread: thread::Id, ts: usize, name: String, ph: &'static str) {
+        let tid = thread.as_usize();
+
+        self.threads = ::std::cmp::max(self.threads, tid + 1);
+
+        self.events.push(Event {
+            name,
+            ph,
+            ts,
+            pid: 0,
+            tid,
+            s: if ph == "i" { Some("t") } else { None },
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.threads = 0;
+    }
+
+    /// Write the timeline as a JSON object with a `traceEvents` array
+    pub fn write_json<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        let names = (0..self.threads).map(|tid| {
+            serde_json::to_value(Metadata {
+                name: "thread_name",
+                ph: "M",
+                pid: 0,
+                tid,
+                args: ThreadName { name: format!("thread {}", tid) },
+            })
+        });
+
+        let events = self.events.iter().map(serde_json::to_value);
+
+        let trace = Trace {
+            trace_events: names.chain(events).collect::<Result<_, _>>()?,
+        };
+
+        serde_json::to_writer_pretty(out, &trace)?;
+        Ok(())
+    }
+}
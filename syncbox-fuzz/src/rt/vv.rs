@@ -1,17 +1,19 @@
+use rt::arena::{Arena, Slice};
 use rt::thread;
 
 use std::cmp;
 use std::ops;
 
-#[derive(Debug, Clone, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+/// Version vector allocated in the execution's arena
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct VersionVec {
-    versions: Box<[usize]>,
+    versions: Slice<usize>,
 }
 
 impl VersionVec {
-    pub fn new(max_threads: usize) -> VersionVec {
+    pub fn new(max_threads: usize, arena: &Arena) -> VersionVec {
         VersionVec {
-            versions: vec![0; max_threads].into_boxed_slice(),
+            versions: arena.slice(max_threads),
         }
     }
 
@@ -23,14 +25,18 @@ impl VersionVec {
             })
     }
 
-    pub fn len(&self) -> usize {
-        self.versions.len()
-    }
-
     pub fn inc(&mut self, id: thread::Id) {
         self.versions[id.as_usize()] += 1;
     }
 
+    /// Returns `true` if every version is at most the matching version in
+    /// `other`, i.e. `self` happens before `other`.
+    pub fn precedes(&self, other: &VersionVec) -> bool {
+        self.versions.iter()
+            .zip(other.versions.iter())
+            .all(|(a, b)| a <= b)
+    }
+
     pub fn join(&mut self, other: &VersionVec) {
         for (i, &version) in other.versions.iter().enumerate() {
             self.versions[i] = cmp::max(self.versions[i], version);
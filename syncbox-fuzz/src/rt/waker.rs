@@ -0,0 +1,48 @@
+use rt::thread;
+
+use std::task::{RawWaker, RawWakerVTable, Waker};
+
+/// Vtable for wakers that are tracked as live task handles.
+static VTABLE: RawWakerVTable = RawWakerVTable::new(
+    clone,
+    wake,
+    wake_by_ref,
+    drop_waker);
+
+/// Vtable for the waker owned by `block_on`. It is only ever lent out through
+/// `Context`, so it does not count as a task handle held by another thread.
+static ROOT_VTABLE: RawWakerVTable = RawWakerVTable::new(
+    clone,
+    wake_by_ref,
+    wake_by_ref,
+    drop_root);
+
+/// Create the `Waker` used to poll a future on the given thread.
+///
+/// The waker does not own any data, the thread identifier is stored directly
+/// in the data pointer.
+pub fn root(thread: thread::Id) -> Waker {
+    let raw = RawWaker::new(thread.as_usize() as *const (), &ROOT_VTABLE);
+    unsafe { Waker::from_raw(raw) }
+}
+
+unsafe fn clone(data: *const ()) -> RawWaker {
+    thread::Id::from_usize(data as usize).task_ref();
+    RawWaker::new(data, &VTABLE)
+}
+
+unsafe fn wake(data: *const ()) {
+    wake_by_ref(data);
+    drop_waker(data);
+}
+
+unsafe fn wake_by_ref(data: *const ()) {
+    thread::Id::from_usize(data as usize).future_notify();
+}
+
+unsafe fn drop_waker(data: *const ()) {
+    thread::Id::from_usize(data as usize).task_unref();
+}
+
+unsafe fn drop_root(_: *const ()) {
+}
@@ -0,0 +1,228 @@
+//! Wakeup trees of optimal DPOR.
+//!
+//! Instead of a set of threads to backtrack to, each schedule branch point
+//! keeps a tree of step sequences left to explore from it. A race is reversed
+//! by inserting the steps that do not depend on the first racing step,
+//! followed by the second one. Executions started from the tree follow its
+//! sequences, so the reversed race is explored right away instead of being
+//! approximated by a single thread that may lead elsewhere.
+//!
+//! A sequence is not inserted when a thread that was already explored from
+//! the branch point is able to start it, or when the tree already holds a
+//! sequence that leads to an equivalent execution.
+
+/// A step taken by a thread from a schedule branch point
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    /// Thread taking the step
+    pub thread: usize,
+
+    /// Object operated on by the step, if any
+    pub object: Option<usize>,
+
+    /// True if the operation is an atomic load. Loads of the same object are
+    /// independent of each other.
+    pub load: bool,
+}
+
+/// A node of a wakeup tree. The sequences of the tree are the paths from its
+/// roots to its leaves, explored in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Wakeup {
+    pub step: Step,
+    pub children: Vec<Wakeup>,
+}
+
+/// A sequence of steps reversing a race, along with the happens-before
+/// relation between them
+#[derive(Debug)]
+pub struct Sequence {
+    steps: Vec<Step>,
+
+    /// `before[j][i]` is set if step `i` happens before step `j`
+    before: Vec<Vec<bool>>,
+}
+
+impl Step {
+    /// Returns `true` if the outcome depends on the order in which the two
+    /// steps are taken. Steps that operate on no object are only ordered by
+    /// the threads they are taken by.
+    pub fn is_dependent(&self, other: &Step) -> bool {
+        match (self.object, other.object) {
+            (Some(a), Some(b)) => a == b && !(self.load && other.load),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the two steps may be swapped. Steps that operate on
+    /// no object may touch state that is not modeled, so they are never
+    /// swapped.
+    fn commutes(&self, other: &Step) -> bool {
+        self.object.is_some() && other.object.is_some() && !self.is_dependent(other)
+    }
+}
+
+impl Sequence {
+    /// Create a sequence from `steps` in the order they are taken.
+    ///
+    /// `ordered(i, j)` returns `true` if step `i` happens before step `j`
+    /// through synchronization. Steps of the same thread and dependent steps
+    /// are always ordered.
+    pub fn new<F>(steps: Vec<Step>, ordered: F) -> Sequence
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        let mut before: Vec<Vec<bool>> = Vec::with_capacity(steps.len());
+
+        for j in 0..steps.len() {
+            let mut preds = vec![false; j];
+
+            for i in (0..j).rev() {
+                if preds[i] {
+                    continue;
+                }
+
+                let direct = steps[i].thread == steps[j].thread
+                    || steps[i].is_dependent(&steps[j])
+                    || ordered(i, j);
+
+                if direct {
+                    preds[i] = true;
+
+                    for k in 0..i {
+                        if before[i][k] {
+                            preds[k] = true;
+                        }
+                    }
+                }
+            }
+
+            before.push(preds);
+        }
+
+        Sequence { steps, before }
+    }
+
+    /// Returns `true` if the sequence is able to start with `step`, which is
+    /// the next step of its thread. Either the thread's first step in the
+    /// sequence happens after no other step, or the thread takes no step in
+    /// the sequence and `step` may be swapped with all of them.
+    pub fn starts_with(&self, step: &Step) -> bool {
+        let remaining = vec![true; self.steps.len()];
+        self.take(&remaining, step).is_some()
+    }
+
+    /// Insert the sequence into the wakeup tree `roots`, unless a sequence of
+    /// the tree already leads to an equivalent execution.
+    ///
+    /// The sequence is added after the longest path of the tree it extends.
+    /// If it starts a new root, `allow` decides whether its thread may be
+    /// explored from the branch point. Returns the thread of the new root.
+    pub fn insert<F>(&self, roots: &mut Vec<Wakeup>, allow: F) -> Option<usize>
+    where
+        F: FnOnce(usize) -> bool,
+    {
+        let mut remaining = vec![true; self.steps.len()];
+
+        if self.is_covered(roots, &remaining) {
+            return None;
+        }
+
+        let mut nodes = roots;
+        let mut depth = 0;
+
+        loop {
+            let next = nodes.iter()
+                .enumerate()
+                .filter_map(|(i, node)| {
+                    self.take(&remaining, &node.step).map(|rest| (i, rest))
+                })
+                .next();
+
+            let (i, rest) = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            remaining = rest;
+            depth += 1;
+
+            let current = nodes;
+            nodes = &mut current[i].children;
+        }
+
+        let steps: Vec<_> = self.steps.iter()
+            .zip(remaining.iter())
+            .filter(|&(_, &remaining)| remaining)
+            .map(|(step, _)| *step)
+            .collect();
+
+        let thread = steps.first()?.thread;
+
+        if depth == 0 && !allow(thread) {
+            return None;
+        }
+
+        let branch = steps.into_iter().rev().fold(None, |child, step| {
+            Some(Wakeup {
+                step,
+                children: child.into_iter().collect(),
+            })
+        });
+
+        nodes.extend(branch);
+
+        if depth == 0 {
+            Some(thread)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if a leaf of the tree `nodes` leads to an execution that
+    /// extends the `remaining` steps.
+    fn is_covered(&self, nodes: &[Wakeup], remaining: &[bool]) -> bool {
+        nodes.iter().any(|node| {
+            match self.take(remaining, &node.step) {
+                Some(rest) => {
+                    node.children.is_empty() || self.is_covered(&node.children, &rest)
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Take `step` first, leaving the rest of the `remaining` steps. Returns
+    /// `None` if `step` cannot be taken before them without changing their
+    /// outcome.
+    fn take(&self, remaining: &[bool], step: &Step) -> Option<Vec<bool>> {
+        let first = (0..self.steps.len())
+            .find(|&i| remaining[i] && self.steps[i].thread == step.thread);
+
+        match first {
+            Some(j) => {
+                let initial = self.steps[j] == *step
+                    && (0..j).all(|i| !remaining[i] || !self.before[j][i]);
+
+                if !initial {
+                    return None;
+                }
+
+                let mut rest = remaining.to_vec();
+                rest[j] = false;
+                Some(rest)
+            }
+            None => {
+                let commutes = (0..self.steps.len())
+                    .filter(|&i| remaining[i])
+                    .all(|i| step.commutes(&self.steps[i]));
+
+                if commutes {
+                    Some(remaining.to_vec())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Custom runtimes.
+//!
+//! A runtime decides how model threads are run, for example as coroutines or
+//! OS threads. It is selected with `Runtime::Custom`, which creates a
+//! `Scheduler` for each `fuzz` run, each worker and each shrunk failure.
+//!
+//! Model threads are numbered in spawn order, the thread passed to `run`
+//! being thread 0. Only one model thread runs at a time: the runtime keeps
+//! resuming the thread returned by `Execution::active_thread` until it
+//! returns `None`. A spawned thread must not start running before it becomes
+//! the active thread.
+
+use rt::{self, FnBox};
+
+use std::fmt;
+
+/// Runs the model threads of an execution.
+///
+/// `switch`, `spawn` and `with_execution` are called by the active model
+/// thread, on whichever OS thread the runtime runs it on.
+pub trait Scheduler {
+    /// Run an execution, starting with `f` as thread 0.
+    ///
+    /// Returns once no thread is active. A panic in a model thread must be
+    /// resumed from here. While shrinking a failure, `run` is called again
+    /// after such a panic, so the threads of the failed execution must not be
+    /// reused.
+    fn run(&self, execution: Execution, f: Task);
+
+    /// Suspend the calling model thread and resume the active one. Returns
+    /// once the calling thread is active again.
+    fn switch(&self);
+
+    /// Queue a new model thread
+    fn spawn(&self, f: Task);
+
+    /// Call `f` with the execution passed to `run`
+    fn with_execution(&self, f: &mut FnMut(Execution));
+}
+
+/// Handle to the execution being run. Only valid during `Scheduler::run`.
+#[derive(Clone, Copy)]
+pub struct Execution {
+    ptr: *mut rt::Execution,
+}
+
+/// Body of a model thread
+pub struct Task {
+    f: Box<FnBox>,
+    scheduler: *const Scheduler,
+}
+
+/// Model threads only run one at a time.
+unsafe impl Send for Execution {}
+unsafe impl Send for Task {}
+
+impl Execution {
+    pub(crate) fn new(execution: &mut rt::Execution) -> Execution {
+        Execution { ptr: execution as *mut _ }
+    }
+
+    pub(crate) unsafe fn get<'a>(self) -> &'a mut rt::Execution {
+        &mut *self.ptr
+    }
+
+    /// Returns the model thread to resume, or `None` once all threads have
+    /// terminated.
+    pub fn active_thread(&self) -> Option<usize> {
+        let execution = unsafe { &*self.ptr };
+
+        if !execution.threads.is_active() {
+            return None;
+        }
+
+        Some(execution.threads.active_id().as_usize())
+    }
+}
+
+impl fmt::Debug for Execution {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Execution")
+            .field("active_thread", &self.active_thread())
+            .finish()
+    }
+}
+
+impl Task {
+    pub(crate) fn new(f: Box<FnBox>, scheduler: *const Scheduler) -> Task {
+        Task { f, scheduler }
+    }
+
+    /// Run the model thread to completion, switching out whenever it calls
+    /// `Scheduler::switch`.
+    pub fn run(self) {
+        rt::enter_custom(self.scheduler);
+        self.f.call();
+    }
+}
+
+impl fmt::Debug for Task {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Task")
+            .finish()
+    }
+}
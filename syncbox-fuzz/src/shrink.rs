@@ -0,0 +1,202 @@
+//! Minimizing failing executions.
+//!
+//! A failing execution is replayed with one of its preemptions removed or one
+//! of its stale reads replaced by a read of the latest write, either keeping
+//! the rest of its choices or falling back to the default choices. Candidates
+//! that fail with the same message and are simpler replace the execution,
+//! until no candidate is.
+
+use fuzz::Builder;
+use rt::{self, Execution, Path, Scheduler};
+use rt::path::Choice;
+
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+/// Bounds the number of replayed candidates
+const MAX_RUNS: usize = 1_000;
+
+type Hook = Box<Fn(&panic::PanicHookInfo) + Sync + Send + 'static>;
+
+thread_local!(static QUIET: Cell<bool> = Cell::new(false));
+
+/// Number of shrinks in progress and the panic hook they replaced
+static PREV_HOOK: Mutex<Option<(usize, Arc<Hook>)>> = Mutex::new(None);
+
+/// Returns the simplest failing execution found from the failing `path`.
+///
+/// `message` is the panic message of the failure. Returns `None` if replaying
+/// `path` does not fail the same way.
+pub(crate) fn shrink<F>(builder: &Builder, path: &Path, f: &Arc<F>, message: Option<&str>)
+    -> Option<Execution>
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let _hook = QuietHook::install();
+    let mut scheduler = builder.new_scheduler();
+
+    let mut best = replay(builder, &mut scheduler, path.choices(), f, message)?;
+    let mut runs = 1;
+
+    'outer:
+    while runs < MAX_RUNS {
+        let choices = best.path.choices();
+
+        for i in 0..choices.len() {
+            let simpler = match best.path.simpler_choice(i) {
+                Some(simpler) => simpler,
+                None => continue,
+            };
+
+            let mut prefix = choices[..i].to_vec();
+            prefix.push(simpler);
+
+            let mut guide = prefix.clone();
+            guide.extend_from_slice(&choices[i + 1..]);
+
+            for candidate in vec![guide, prefix] {
+                if runs == MAX_RUNS {
+                    break 'outer;
+                }
+
+                runs += 1;
+
+                if let Some(execution) = replay(builder, &mut scheduler, candidate, f, message) {
+                    if execution.path.complexity() < best.path.complexity() {
+                        best = execution;
+                        continue 'outer;
+                    }
+                }
+            }
+        }
+
+        break;
+    }
+
+    Some(best)
+}
+
+/// Returns the message of a panic payload
+pub(crate) fn message(err: &Box<Any + Send>) -> Option<&str> {
+    err.downcast_ref::<&str>()
+        .map(|message| *message)
+        .or_else(|| err.downcast_ref::<String>().map(|message| &message[..]))
+}
+
+/// Run the execution guided by `choices`. Returns the execution if it failed
+/// with `message`.
+fn replay<F>(
+    builder: &Builder,
+    scheduler: &mut Scheduler,
+    choices: Vec<Option<Choice>>,
+    f: &Arc<F>,
+    message: Option<&str>,
+) -> Option<Execution>
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let mut execution = Execution::new(builder.max_threads, builder.max_memory);
+
+    execution.path = Path::guided(choices);
+    execution.dpor = builder.dpor;
+    execution.graph = builder.new_graph();
+    execution.timeline = builder.new_timeline();
+    execution.arena.set_growable(builder.grow_memory);
+
+    let f = f.clone();
+
+    let res = quiet(|| {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            scheduler.run(&mut execution, move || {
+                f();
+                rt::thread_done();
+            });
+        }))
+    });
+
+    match res {
+        Ok(()) => None,
+        Err(ref err) if message.is_some() && self::message(err) != message => None,
+        Err(_) => Some(execution),
+    }
+}
+
+/// Run `f` without printing the panics of this thread
+fn quiet<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            QUIET.with(|quiet| quiet.set(false));
+        }
+    }
+
+    QUIET.with(|quiet| quiet.set(true));
+    let _reset = Reset;
+
+    f()
+}
+
+/// Panic hook silencing quiet threads while shrinking. The previous hook is
+/// restored once the last shrink in progress is done.
+struct QuietHook;
+
+impl QuietHook {
+    fn install() -> QuietHook {
+        let mut prev = PREV_HOOK.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match *prev {
+            Some((ref mut shrinks, _)) => *shrinks += 1,
+            None => {
+                let hook = Arc::new(panic::take_hook());
+                let next = hook.clone();
+
+                panic::set_hook(Box::new(move |info| {
+                    if !QUIET.try_with(|quiet| quiet.get()).unwrap_or(false) {
+                        next(info);
+                    }
+                }));
+
+                *prev = Some((1, hook));
+            }
+        }
+
+        QuietHook
+    }
+}
+
+impl Drop for QuietHook {
+    fn drop(&mut self) {
+        let mut prev = PREV_HOOK.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let done = match *prev {
+            Some((ref mut shrinks, _)) => {
+                *shrinks -= 1;
+                *shrinks == 0
+            }
+            None => false,
+        };
+
+        // The hook cannot be replaced while panicking. It is then left in
+        // place, calling the previous one.
+        if !done || thread::panicking() {
+            return;
+        }
+
+        let (_, hook) = prev.take().unwrap();
+
+        // Drops the quiet hook and its reference to the previous one
+        drop(panic::take_hook());
+
+        match Arc::try_unwrap(hook) {
+            Ok(hook) => panic::set_hook(hook),
+            Err(hook) => panic::set_hook(Box::new(move |info| hook(info))),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Program state hashing.
+//!
+//! When `Builder::cache_states` is set, the checker hashes the program state
+//! at every new scheduling branch point. Once all executions from a state have
+//! been explored, executions reaching the same state again stop exploring
+//! alternatives. This keeps spin loops that revisit identical states from
+//! blowing up the search.
+//!
+//! The hash covers the state of each thread, the values written to atomics
+//! and the operations performed on other objects. Everything else that
+//! influences how the threads behave from a branch point on has to be provided
+//! by the test by implementing `State` and registering it with `track`. This
+//! includes the progress of each thread when it is not visible in the shared
+//! state. States that hash equally while behaving differently lead to missed
+//! executions.
+//!
+//! Races found after reaching an explored state are still reversed. The hash
+//! does not cover the happens-before relation between threads though, so even
+//! with all shared state registered, caching may miss executions that DPOR
+//! alone explores. It is disabled by default.
+
+use rt;
+
+use std::hash::Hasher;
+use std::sync::Arc;
+
+/// Shared state included in the program state hash
+pub trait State {
+    /// Feed the state into `hasher`.
+    ///
+    /// This is called while the checker is scheduling threads, so it must not
+    /// operate on syncbox-fuzz types.
+    fn hash_state(&self, hasher: &mut Hasher);
+}
+
+/// Include `state` in the program state hash for the rest of the execution.
+pub fn track<T>(state: &Arc<T>)
+where
+    T: State + 'static,
+{
+    let state = state.clone() as Arc<State>;
+
+    rt::execution(|execution| {
+        execution.states.push(state);
+    });
+}
@@ -1,7 +1,12 @@
 use rt::{self, thread, Execution, Synchronize};
+use rt::arena::Slice;
+use rt::graph::Edge;
 use rt::object::{self, Object};
+use rt::path::Kind;
 
 use std::cell::RefCell;
+use std::fmt::Debug;
+use std::hash::Hash;
 use std::sync::atomic::Ordering;
 
 /// An atomic value
@@ -24,27 +29,38 @@ struct Write<T> {
 
     /// True when the write was done with `SeqCst` ordering
     seq_cst: bool,
+
+    /// Happens-before graph node of the write
+    node: Option<usize>,
 }
 
 #[derive(Debug)]
-struct FirstSeen(Vec<Option<usize>>);
+struct FirstSeen(Slice<Option<usize>>);
 
 impl<T> Atomic<T>
 where
-    T: Copy + PartialEq,
+    T: Copy + PartialEq + Hash + Debug,
 {
     pub fn new(value: T) -> Atomic<T> {
         rt::execution(|execution| {
+            let object = execution.objects.insert(Object::atomic());
+            execution.objects.record_write(object, value);
+
+            let node = execution.trace(|| {
+                format!("atomic {}: init {:?}", object.as_usize(), value)
+            });
+
             let writes = vec![Write {
                 value,
-                sync: Synchronize::new(execution.max_threads),
+                sync: Synchronize::new(execution),
                 first_seen: FirstSeen::new(execution),
                 seq_cst: false,
+                node,
             }];
 
             Atomic {
                 writes: RefCell::new(writes),
-                object: execution.objects.insert(Object::atomic()),
+                object,
             }
         })
     }
@@ -55,7 +71,13 @@ where
 
         synchronize(|execution| {
             // Pick a write that satisfies causality and specified ordering.
-            let write = pick_write(&mut writes[..], execution, order);
+            let write = pick_write(&mut writes[..], self.object, execution, order);
+
+            let node = execution.trace(|| {
+                format!("atomic {}: load({:?}) = {:?}", self.object.as_usize(), order, write.value)
+            });
+
+            execution.trace_edge(write.node, node, Edge::ReadsFrom);
             write.first_seen.touch(&execution.threads);
             write.sync.sync_read(execution, order);
             write.value
@@ -67,7 +89,11 @@ where
         let mut writes = self.writes.borrow_mut();
 
         synchronize(|execution| {
-            do_write(val, &mut *writes, execution, order);
+            let node = execution.trace(|| {
+                format!("atomic {}: store({:?}) {:?}", self.object.as_usize(), order, val)
+            });
+
+            do_write(val, self.object, &mut *writes, execution, order, node);
         });
     }
 
@@ -82,14 +108,22 @@ where
         let mut writes = self.writes.borrow_mut();
 
         synchronize(|execution| {
-            let old = {
+            let (old, read) = {
                 let write = writes.last_mut().unwrap();
                 write.first_seen.touch(&execution.threads);
-                write.sync.sync_read(execution, order);
-                write.value
+                (write.value, write.node)
             };
 
-            do_write(f(old), &mut *writes, execution, order);
+            let new = f(old);
+
+            let node = execution.trace(|| {
+                format!("atomic {}: rmw({:?}) {:?} -> {:?}", self.object.as_usize(), order, old, new)
+            });
+
+            execution.trace_edge(read, node, Edge::ReadsFrom);
+            writes.last_mut().unwrap().sync.sync_read(execution, order);
+
+            do_write(new, self.object, &mut *writes, execution, order, node);
             old
         })
     }
@@ -125,19 +159,34 @@ where
         let mut writes = self.writes.borrow_mut();
 
         synchronize(|execution| {
-            {
+            let node = {
                 let write = writes.last_mut().unwrap();
                 write.first_seen.touch(&execution.threads);
 
                 if write.value != current {
+                    let node = execution.trace(|| {
+                        format!(
+                            "atomic {}: cas({:?}) failed, read {:?}",
+                            self.object.as_usize(), failure, write.value)
+                    });
+
+                    execution.trace_edge(write.node, node, Edge::ReadsFrom);
                     write.sync.sync_read(execution, failure);
                     return Err(write.value);
                 }
 
+                let node = execution.trace(|| {
+                    format!(
+                        "atomic {}: cas({:?}) {:?} -> {:?}",
+                        self.object.as_usize(), success, current, new)
+                });
+
+                execution.trace_edge(write.node, node, Edge::ReadsFrom);
                 write.sync.sync_read(execution, success);
-            }
+                node
+            };
 
-            do_write(new, &mut *writes, execution, success);
+            do_write(new, self.object, &mut *writes, execution, success, node);
             Ok(current)
         })
     }
@@ -145,6 +194,7 @@ where
 
 fn pick_write<'a, T>(
     writes: &'a mut [Write<T>],
+    object: object::Id,
     execution: &mut Execution,
     order: Ordering,
 ) -> &'a mut Write<T>
@@ -152,7 +202,7 @@ fn pick_write<'a, T>(
     let mut in_causality = false;
     let threads = &mut execution.threads;
 
-    let next = execution.path.branch_write({
+    let next = execution.path.branch_write(Kind::Load(object.as_usize()), {
         writes.iter()
             .enumerate()
             .rev()
@@ -172,17 +222,22 @@ fn pick_write<'a, T>(
     &mut writes[next]
 }
 
-fn do_write<T>(
+fn do_write<T: Hash>(
     value: T,
+    object: object::Id,
     writes: &mut Vec<Write<T>>,
     execution: &mut Execution,
-    order: Ordering)
+    order: Ordering,
+    node: Option<usize>)
 {
+    execution.objects.record_write(object, &value);
+
     let mut write = Write {
         value,
         sync: writes.last().unwrap().sync.clone(),
         first_seen: FirstSeen::new(execution),
         seq_cst: is_seq_cst(order),
+        node,
     };
 
     write.sync.sync_write(execution, order);
@@ -209,19 +264,13 @@ fn is_seq_cst(order: Ordering) -> bool {
 
 impl FirstSeen {
     fn new(execution: &mut Execution) -> FirstSeen {
-        let mut first_seen = FirstSeen(vec![]);
+        let mut first_seen = FirstSeen(execution.arena.slice(execution.max_threads));
         first_seen.touch(&execution.threads);
 
         first_seen
     }
 
     fn touch(&mut self, threads: &thread::Set) {
-        let happens_before = &threads.active().causality;
-
-        if self.0.len() < happens_before.len() {
-            self.0.resize(happens_before.len(), None);
-        }
-
         if self.0[threads.active_id().as_usize()].is_none() {
             self.0[threads.active_id().as_usize()] = Some(threads.active_atomic_version());
         }
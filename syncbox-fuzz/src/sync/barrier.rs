@@ -0,0 +1,96 @@
+use rt::{self, thread, VersionVec};
+use rt::object::{self, Object};
+use rt::path::Kind;
+
+use std::cell::RefCell;
+
+pub struct Barrier {
+    object: object::Id,
+    num_threads: usize,
+    state: RefCell<State>,
+}
+
+#[derive(Debug)]
+pub struct BarrierWaitResult(bool);
+
+struct State {
+    /// Threads waiting on the current generation
+    waiters: Vec<thread::Id>,
+
+    /// Incremented each time the barrier releases its waiters
+    generation: usize,
+
+    /// Leader of the last released generation
+    leader: Option<thread::Id>,
+
+    /// Joined causality of all threads waiting on the current generation
+    causality: VersionVec,
+}
+
+impl Barrier {
+    pub fn new(n: usize) -> Barrier {
+        rt::execution(|execution| {
+            Barrier {
+                object: execution.objects.insert(Object::barrier()),
+                num_threads: n,
+                state: RefCell::new(State {
+                    waiters: vec![],
+                    generation: 0,
+                    leader: None,
+                    causality: VersionVec::new(execution.max_threads, &execution.arena),
+                }),
+            }
+        })
+    }
+
+    pub fn wait(&self) -> BarrierWaitResult {
+        self.object.branch();
+
+        let thread_id = thread::Id::current();
+
+        let generation = rt::execution(|execution| {
+            let mut state = self.state.borrow_mut();
+
+            state.waiters.push(thread_id);
+            state.causality.join(&execution.threads.active().causality);
+
+            if state.waiters.len() < self.num_threads {
+                return Some(state.generation);
+            }
+
+            // This is the last thread to arrive. All waiters' causality is
+            // joined and the leader is picked as an explored branch.
+            let state = &mut *state;
+
+            execution.threads.active_mut().causality.join(&state.causality);
+
+            let leader = execution.path.branch_write(Kind::Leader(self.object.as_usize()), 0..state.waiters.len());
+            state.leader = Some(state.waiters[leader]);
+            state.generation += 1;
+
+            // Unparking the waiters transfers the joined causality.
+            for waiter in state.waiters.drain(..) {
+                execution.unpark_thread(waiter);
+            }
+
+            state.causality = VersionVec::new(execution.max_threads, &execution.arena);
+
+            None
+        });
+
+        if let Some(generation) = generation {
+            while generation == self.state.borrow().generation {
+                rt::park();
+            }
+        }
+
+        let is_leader = self.state.borrow().leader == Some(thread_id);
+        BarrierWaitResult(is_leader)
+    }
+}
+
+impl BarrierWaitResult {
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
@@ -27,7 +27,7 @@ impl<T> CausalCell<T> {
             let v = self.version.borrow();
 
             assert!(
-                *v <= execution.threads.active().causality,
+                v.precedes(&execution.threads.active().causality),
                 "cell={:?}; thread={:?}",
                 *v, execution.threads.active().causality);
         });
@@ -45,7 +45,7 @@ impl<T> CausalCell<T> {
             let mut v = self.version.borrow_mut();
 
             assert!(
-                *v <= execution.threads.active().causality,
+                v.precedes(&execution.threads.active().causality),
                 "cell={:?}; thread={:?}",
                 *v, execution.threads.active().causality);
 
@@ -1,6 +1,8 @@
 use super::{MutexGuard, LockResult};
 use rt::{self, thread};
+use rt::graph::Edge;
 use rt::object::{self, Object};
+use time::{self, Instant};
 
 use std::cell::RefCell;
 use std::collections::VecDeque;
@@ -11,6 +13,7 @@ pub struct Condvar {
     waiters: RefCell<VecDeque<thread::Id>>,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct WaitTimeoutResult(bool);
 
 impl Condvar {
@@ -27,6 +30,7 @@ impl Condvar {
         -> LockResult<MutexGuard<'a, T>>
     {
         self.object.branch();
+        self.trace("wait");
 
         self.waiters.borrow_mut()
             .push_back(thread::Id::current());
@@ -40,20 +44,57 @@ impl Condvar {
         Ok(guard)
     }
 
-    pub fn wait_timeout<'a, T>(&self, _guard: MutexGuard<'a, T>, _dur: Duration)
+    pub fn wait_timeout<'a, T>(&self, mut guard: MutexGuard<'a, T>, dur: Duration)
         -> LockResult<(MutexGuard<'a, T>, WaitTimeoutResult)>
     {
-        unimplemented!();
+        self.object.branch();
+        self.trace("wait_timeout");
+
+        let thread_id = thread::Id::current();
+        let deadline = Instant::now() + dur;
+
+        self.waiters.borrow_mut()
+            .push_back(thread_id);
+
+        guard.release();
+
+        let timed_out = time::park_until(deadline);
+
+        if timed_out {
+            self.waiters.borrow_mut()
+                .retain(|&waiter| waiter != thread_id);
+        }
+
+        guard.acquire();
+
+        Ok((guard, WaitTimeoutResult(timed_out)))
     }
 
     pub fn notify_one(&self) {
         self.object.branch();
+        let node = self.trace("notify_one");
 
         let th = self.waiters.borrow_mut()
             .pop_front();
 
         if let Some(th) = th {
+            rt::execution(|execution| {
+                execution.trace_edge_to_next(node, th, Edge::Condvar);
+            });
+
             th.unpark();
         }
     }
+
+    fn trace(&self, operation: &str) -> Option<usize> {
+        rt::execution(|execution| {
+            execution.trace(|| format!("condvar {}: {}", self.object.as_usize(), operation))
+        })
+    }
+}
+
+impl WaitTimeoutResult {
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
 }
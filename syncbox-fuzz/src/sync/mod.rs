@@ -1,8 +1,10 @@
+mod barrier;
 mod causal;
 pub mod atomic;
 mod condvar;
 mod mutex;
 
+pub use self::barrier::{Barrier, BarrierWaitResult};
 pub use self::causal::CausalCell;
 pub use self::condvar::{Condvar, WaitTimeoutResult};
 pub use self::mutex::{Mutex, MutexGuard};
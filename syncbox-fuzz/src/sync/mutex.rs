@@ -1,4 +1,5 @@
 use rt::{self, thread};
+use rt::graph::Edge;
 use rt::object::{self, Object};
 
 use std::cell::{Cell, RefCell, RefMut};
@@ -10,6 +11,9 @@ pub struct Mutex<T> {
     data: RefCell<T>,
     lock: Cell<Option<thread::Id>>,
     object: object::Id,
+
+    /// Happens-before graph node of the last unlock
+    released: Cell<Option<usize>>,
 }
 
 pub struct MutexGuard<'a, T: 'a> {
@@ -24,6 +28,7 @@ impl<T> Mutex<T> {
                 data: RefCell::new(data),
                 lock: Cell::new(None),
                 object: execution.objects.insert(Object::mutex()),
+                released: Cell::new(None),
             }
         })
     }
@@ -43,6 +48,10 @@ impl<T> Mutex<T> {
         self.object.branch_acquire(self.is_locked());
 
         rt::execution(|execution| {
+            let node = execution.trace(|| format!("mutex {}: lock", self.object.as_usize()));
+            execution.trace_edge(self.released.get(), node, Edge::Mutex);
+            execution.timeline_begin(|| format!("mutex {} held", self.object.as_usize()));
+
             execution.seq_cst();
 
             let thread_id = execution.threads.active_id();
@@ -70,6 +79,10 @@ impl<T> Mutex<T> {
         self.lock.set(None);
 
         rt::execution(|execution| {
+            let node = execution.trace(|| format!("mutex {}: unlock", self.object.as_usize()));
+            self.released.set(node);
+            execution.timeline_end(|| format!("mutex {} held", self.object.as_usize()));
+
             execution.seq_cst();
 
             let thread_id = execution.threads.active_id();
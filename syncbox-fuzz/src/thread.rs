@@ -1,10 +1,12 @@
 use rt::{self, oneshot};
+use rt::graph::Edge;
 use rt::object::{self, Object};
 
 use std::thread as std;
 
 pub struct JoinHandle<T> {
-    rx: oneshot::Receiver<std::Result<T>>,
+    /// Result of the thread and its last happens-before graph node
+    rx: oneshot::Receiver<(std::Result<T>, Option<usize>)>,
     object: object::Id,
 }
 
@@ -22,9 +24,10 @@ where
     rt::spawn(move || {
         let res = Ok(f());
 
+        let exit = rt::execution(|execution| execution.trace(|| "exit".to_string()));
         object.branch_unpark(true);
 
-        tx.send(res);
+        tx.send((res, exit));
     });
 
     JoinHandle {
@@ -35,7 +38,13 @@ where
 
 impl<T> JoinHandle<T> {
     pub fn join(self) -> std::Result<T> {
-        let ret = self.rx.recv();
+        let (ret, exit) = self.rx.recv();
+
+        rt::execution(|execution| {
+            let node = execution.trace(|| "join".to_string());
+            execution.trace_edge(exit, node, Edge::Join);
+        });
+
         self.object.branch_park(true);
         ret
     }
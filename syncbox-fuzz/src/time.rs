@@ -0,0 +1,77 @@
+//! Virtual time.
+//!
+//! Each execution has its own clock, which starts at zero. The clock does not
+//! follow wall-clock time. It only advances when all threads are blocked, or
+//! when a thread waiting on a deadline is explored timing out.
+
+use rt;
+
+use std::ops;
+
+pub use std::time::Duration;
+
+/// A measurement of the execution's virtual clock.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    pub fn now() -> Instant {
+        rt::execution(|execution| Instant(execution.time))
+    }
+
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.0.checked_sub(earlier.0)
+            .unwrap_or(Duration::from_millis(0))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+}
+
+impl ops::Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, other: Duration) -> Instant {
+        Instant(self.0 + other)
+    }
+}
+
+impl ops::AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, other: Duration) {
+        self.0 += other;
+    }
+}
+
+impl ops::Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, other: Duration) -> Instant {
+        Instant(self.0 - other)
+    }
+}
+
+impl ops::Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, other: Instant) -> Duration {
+        self.duration_since(other)
+    }
+}
+
+/// Puts the current thread to sleep for at least the specified duration of
+/// virtual time.
+pub fn sleep(dur: Duration) {
+    let deadline = Instant::now() + dur;
+
+    while Instant::now() < deadline {
+        rt::park_timeout(deadline.0);
+    }
+}
+
+/// Blocks the current thread until it is unparked or `deadline` is reached.
+///
+/// Returns `true` if the deadline was reached.
+pub(crate) fn park_until(deadline: Instant) -> bool {
+    rt::park_timeout(deadline.0)
+}
@@ -0,0 +1,345 @@
+//! Parallel exploration of the execution tree.
+//!
+//! Each worker OS thread runs its own scheduler and execution. Workers that
+//! run out of work wait for a busy worker to split off part of its unexplored
+//! `Path`.
+//!
+//! To write a checkpoint, busy workers pause between executions until all of
+//! them have handed in their path. The queued paths and the paths of the
+//! paused workers then cover the whole unexplored tree.
+
+use fuzz::{Builder, Report};
+use rt::{self, Execution, Path};
+
+use serde_json::{self, Value};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+use std::thread;
+use std::time::Instant;
+
+struct Shared {
+    state: Mutex<State>,
+    condvar: Condvar,
+
+    /// Number of workers waiting for work
+    idle: AtomicUsize,
+
+    /// Set when a worker found a failure or the budget is exhausted
+    stop: AtomicBool,
+
+    /// Set while a checkpoint is being taken
+    pause: AtomicBool,
+
+    /// Total number of iterations across all workers
+    iterations: AtomicUsize,
+}
+
+struct State {
+    /// Paths that have not yet been picked up by a worker
+    queue: VecDeque<Path>,
+
+    /// Set once all workers are idle and there is no more work
+    done: bool,
+
+    /// The path of the first failing execution
+    failure: Option<String>,
+
+    /// Set when exploring stopped because the budget is exhausted
+    exhausted: bool,
+
+    /// Estimated fraction of the tree left unexplored by stopped workers
+    remaining: f64,
+
+    /// Paths left unexplored by stopped workers
+    unexplored: Vec<Value>,
+
+    /// Paths handed in by the workers paused for the current checkpoint
+    paused: Vec<Value>,
+
+    /// Number of checkpoints written
+    checkpoints: usize,
+}
+
+pub fn fuzz<F>(builder: &Builder, paths: Vec<Path>, f: Arc<F>, start: Instant) -> Report
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let num_workers = builder.workers;
+
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: paths.into_iter().collect(),
+            done: false,
+            failure: None,
+            exhausted: false,
+            remaining: 0.0,
+            unexplored: vec![],
+            paused: vec![],
+            checkpoints: 0,
+        }),
+        condvar: Condvar::new(),
+        idle: AtomicUsize::new(0),
+        stop: AtomicBool::new(false),
+        pause: AtomicBool::new(false),
+        iterations: AtomicUsize::new(0),
+    });
+
+    let handles: Vec<_> = (0..num_workers).map(|_| {
+        let builder = builder.clone();
+        let shared = shared.clone();
+        let f = f.clone();
+
+        thread::spawn(move || run_worker(&builder, &shared, f, start))
+    }).collect();
+
+    let mut report = Report::default();
+    let mut panic: Option<Box<Any + Send>> = None;
+
+    for handle in handles {
+        match handle.join() {
+            Ok(worker_report) => report.merge(&worker_report),
+            Err(err) => {
+                panic.get_or_insert(err);
+            }
+        }
+    }
+
+    if let Some(panic) = panic {
+        let mut state = shared.state.lock().unwrap();
+
+        write_unexplored(builder, &mut state);
+
+        if let Some(ref failure) = state.failure {
+            println!(" ===== failing path =====");
+            println!("{}", failure);
+        }
+
+        drop(state);
+        panic::resume_unwind(panic);
+    }
+
+    let mut state = shared.state.lock().unwrap();
+
+    if state.exhausted {
+        write_unexplored(builder, &mut state);
+
+        let remaining = state.queue.iter()
+            .fold(state.remaining, |acc, path| acc + 1.0 - path.coverage());
+
+        report.coverage = (1.0 - remaining).max(0.0);
+        report.print_stopped();
+    } else {
+        report.complete = true;
+        report.coverage = 1.0;
+    }
+
+    report
+}
+
+fn run_worker<F>(builder: &Builder, shared: &Shared, f: Arc<F>, start: Instant) -> Report
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let num_workers = builder.workers;
+    let mut report = Report::default();
+
+    loop {
+        let path = {
+            let mut state = shared.state.lock().unwrap();
+
+            loop {
+                if state.done {
+                    return report;
+                }
+
+                if let Some(path) = state.queue.pop_front() {
+                    break path;
+                }
+
+                // When every worker is waiting, the tree is fully explored.
+                if shared.idle.fetch_add(1, SeqCst) + 1 == num_workers {
+                    state.done = true;
+                    shared.condvar.notify_all();
+                    return report;
+                }
+
+                // One less worker to wait for before writing the checkpoint
+                write_paused(builder, shared, &mut state);
+
+                state = shared.condvar.wait(state).unwrap();
+                shared.idle.fetch_sub(1, SeqCst);
+            }
+        };
+
+        explore(builder, shared, path, &f, start, &mut report);
+    }
+}
+
+fn explore<F>(
+    builder: &Builder,
+    shared: &Shared,
+    path: Path,
+    f: &Arc<F>,
+    start: Instant,
+    report: &mut Report)
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let mut execution = Execution::new(builder.max_threads, builder.max_memory);
+    let mut scheduler = builder.new_scheduler();
+
+    execution.path = path;
+    execution.log = builder.log;
+    execution.dpor = builder.dpor;
+    execution.cache_states = builder.cache_states;
+    execution.sleep_sets = builder.sleep_sets;
+    execution.graph = builder.new_graph();
+    execution.timeline = builder.new_timeline();
+    execution.arena.set_growable(builder.grow_memory);
+
+    loop {
+        if shared.stop.load(Relaxed) {
+            stopped(shared, &execution.path);
+            return;
+        }
+
+        let i = shared.iterations.fetch_add(1, Relaxed);
+
+        if builder.budget_exhausted(i, start) {
+            let mut state = shared.state.lock().unwrap();
+
+            state.remaining += 1.0 - execution.path.coverage();
+            state.unexplored.push(serde_json::to_value(&execution.path).unwrap());
+            state.exhausted = true;
+            state.done = true;
+            shared.stop.store(true, Relaxed);
+            shared.condvar.notify_all();
+            return;
+        }
+
+        let i = i + 1;
+
+        if i % builder.checkpoint_interval == 0 {
+            println!(" ===== iteration {} =====", i);
+
+            if builder.checkpoint_file.is_some() {
+                shared.pause.store(true, SeqCst);
+            }
+        }
+
+        if shared.pause.load(SeqCst) && !pause(builder, shared, &execution.path) {
+            stopped(shared, &execution.path);
+            return;
+        }
+
+        let body = f.clone();
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            scheduler.run(&mut execution, move || {
+                body();
+                rt::thread_done();
+            });
+        }));
+
+        if let Err(err) = res {
+            let mut state = shared.state.lock().unwrap();
+            let first = state.failure.is_none();
+
+            if first {
+                state.failure = Some(serde_json::to_string(&execution.path).unwrap());
+            }
+
+            state.unexplored.push(serde_json::to_value(&execution.path).unwrap());
+
+            state.done = true;
+            shared.stop.store(true, Relaxed);
+            shared.condvar.notify_all();
+
+            drop(state);
+
+            if first {
+                builder.failed(&execution, f, &err);
+            }
+
+            panic::resume_unwind(err);
+        }
+
+        report.record(&execution);
+
+        // Hand part of the remaining tree to an idle worker.
+        let idle = shared.idle.load(SeqCst);
+
+        if idle > 0 {
+            let mut state = shared.state.lock().unwrap();
+
+            if state.queue.len() < idle {
+                if let Some(split) = execution.path.split() {
+                    state.queue.push_back(split);
+                    shared.condvar.notify_one();
+                }
+            }
+        }
+
+        if let Some(next) = execution.step() {
+            execution = next;
+        } else {
+            return;
+        }
+    }
+}
+
+/// Hand in `path` for the current checkpoint and wait for it to be written.
+///
+/// Returns `false` if exploring stopped in the meantime.
+fn pause(builder: &Builder, shared: &Shared, path: &Path) -> bool {
+    let mut state = shared.state.lock().unwrap();
+    let checkpoints = state.checkpoints;
+
+    state.paused.push(serde_json::to_value(path).unwrap());
+    write_paused(builder, shared, &mut state);
+
+    while state.checkpoints == checkpoints && !state.done {
+        state = shared.condvar.wait(state).unwrap();
+    }
+
+    !state.done
+}
+
+/// Write the checkpoint once every busy worker is paused
+fn write_paused(builder: &Builder, shared: &Shared, state: &mut State) {
+    let busy = builder.workers - shared.idle.load(SeqCst);
+
+    if !shared.pause.load(SeqCst) || state.paused.len() < busy {
+        return;
+    }
+
+    let mut paths = mem::replace(&mut state.paused, vec![]);
+    paths.extend(state.queue.iter().map(|path| serde_json::to_value(path).unwrap()));
+
+    builder.write_checkpoint(&paths);
+
+    state.checkpoints += 1;
+    shared.pause.store(false, SeqCst);
+    shared.condvar.notify_all();
+}
+
+/// Record the unexplored part of `path` of a worker that stopped early
+fn stopped(shared: &Shared, path: &Path) {
+    let mut state = shared.state.lock().unwrap();
+
+    state.remaining += 1.0 - path.coverage();
+    state.unexplored.push(serde_json::to_value(path).unwrap());
+}
+
+/// Write the paths left unexplored after stopping to the checkpoint file
+fn write_unexplored(builder: &Builder, state: &mut State) {
+    let mut paths = mem::replace(&mut state.unexplored, vec![]);
+    paths.extend(state.queue.iter().map(|path| serde_json::to_value(path).unwrap()));
+
+    builder.write_checkpoint(&paths);
+}
@@ -0,0 +1,87 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::sync::{Barrier, CausalCell};
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering::Relaxed;
+
+#[test]
+fn barrier_joins_causality() {
+    struct Data {
+        cells: [CausalCell<usize>; 2],
+        barrier: Barrier,
+    }
+
+    syncbox_fuzz::fuzz(|| {
+        let data = Arc::new(Data {
+            cells: [CausalCell::new(0), CausalCell::new(0)],
+            barrier: Barrier::new(2),
+        });
+
+        let th = {
+            let data = data.clone();
+
+            thread::spawn(move || {
+                unsafe { data.cells[1].with_mut(|v| *v = 1) };
+                data.barrier.wait();
+
+                let v = unsafe { data.cells[0].with(|v| *v) };
+                assert_eq!(v, 1);
+            })
+        };
+
+        unsafe { data.cells[0].with_mut(|v| *v = 1) };
+        data.barrier.wait();
+
+        let v = unsafe { data.cells[1].with(|v| *v) };
+        assert_eq!(v, 1);
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn barrier_elects_one_leader() {
+    syncbox_fuzz::fuzz(|| {
+        let data = Arc::new((Barrier::new(2), AtomicUsize::new(0)));
+
+        let th = {
+            let data = data.clone();
+
+            thread::spawn(move || {
+                if data.0.wait().is_leader() {
+                    data.1.fetch_add(1, Relaxed);
+                }
+            })
+        };
+
+        if data.0.wait().is_leader() {
+            data.1.fetch_add(1, Relaxed);
+        }
+
+        th.join().unwrap();
+
+        assert_eq!(1, data.1.load(Relaxed));
+    });
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: !th_leader")]
+fn barrier_explores_both_leaders() {
+    syncbox_fuzz::fuzz(|| {
+        let barrier = Arc::new(Barrier::new(2));
+
+        let th = {
+            let barrier = barrier.clone();
+            thread::spawn(move || barrier.wait().is_leader())
+        };
+
+        let main_leader = barrier.wait().is_leader();
+        let th_leader = th.join().unwrap();
+
+        assert!(main_leader != th_leader);
+        assert!(!th_leader);
+    });
+}
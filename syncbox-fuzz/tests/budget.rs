@@ -0,0 +1,156 @@
+extern crate serde_json;
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use serde_json::Value;
+use std::env;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::Ordering::Relaxed;
+use std::time::Duration;
+
+fn racy() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+        thread::spawn(move || {
+            num.fetch_add(1, Relaxed);
+            num.fetch_add(1, Relaxed);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(4, num.load(Relaxed));
+}
+
+#[test]
+fn complete_without_budget() {
+    let report = Builder::new().fuzz(racy);
+
+    assert!(report.complete);
+    assert_eq!(1.0, report.coverage);
+}
+
+#[test]
+fn max_iterations_stops_early() {
+    let report = Builder::new()
+        .max_iterations(3)
+        .fuzz(racy);
+
+    assert!(!report.complete);
+    assert_eq!(3, report.executions);
+    assert!(report.coverage > 0.0 && report.coverage < 1.0);
+}
+
+#[test]
+fn max_iterations_with_workers() {
+    let report = Builder::new()
+        .workers(2)
+        .max_iterations(3)
+        .fuzz(racy);
+
+    assert!(!report.complete);
+    assert!(report.executions <= 3);
+    assert!(report.coverage < 1.0);
+}
+
+#[test]
+fn zero_time_limit_stops_immediately() {
+    let report = Builder::new()
+        .time_limit(Duration::from_millis(0))
+        .fuzz(racy);
+
+    assert!(!report.complete);
+    assert_eq!(0, report.executions);
+    assert_eq!(0.0, report.coverage);
+}
+
+fn checkpoint_file(name: &str) -> PathBuf {
+    let file = env::temp_dir().join(format!("syncbox-fuzz-budget-{}-{}.json", name, process::id()));
+    let _ = fs::remove_file(&file);
+    file
+}
+
+#[test]
+fn resume_from_checkpoint() {
+    let file = checkpoint_file("single");
+
+    let full = Builder::new().fuzz(racy);
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file = Some(file.clone());
+    builder.max_iterations(3);
+
+    let first = builder.fuzz(racy);
+    assert!(!first.complete);
+
+    builder.max_iterations = None;
+
+    let rest = builder.fuzz(racy);
+    assert!(rest.complete);
+
+    assert_eq!(full.executions, first.executions + rest.executions);
+
+    fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn resume_from_checkpoint_without_kinds() {
+    let file = checkpoint_file("kinds");
+
+    let full = Builder::new().fuzz(racy);
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file = Some(file.clone());
+    builder.max_iterations(3);
+
+    let first = builder.fuzz(racy);
+
+    // Checkpoints written before branch kinds were recorded
+    let mut checkpoint: Value = serde_json::from_reader(File::open(&file).unwrap()).unwrap();
+    checkpoint.as_object_mut().unwrap().remove("kinds").unwrap();
+    serde_json::to_writer(File::create(&file).unwrap(), &checkpoint).unwrap();
+
+    builder.max_iterations = None;
+
+    let rest = builder.fuzz(racy);
+    assert!(rest.complete);
+
+    assert_eq!(full.executions, first.executions + rest.executions);
+    assert_eq!(full.schedule_branches, first.schedule_branches + rest.schedule_branches);
+
+    fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn resume_from_checkpoint_with_workers() {
+    let file = checkpoint_file("workers");
+
+    let mut builder = Builder::new();
+    builder.workers(2);
+    builder.checkpoint_file = Some(file.clone());
+    builder.max_iterations(3);
+
+    let first = builder.fuzz(racy);
+    assert!(!first.complete);
+
+    builder.max_iterations = None;
+
+    let rest = builder.fuzz(racy);
+    assert!(rest.complete);
+
+    fs::remove_file(&file).unwrap();
+
+    // The queued and in-flight paths of both workers were written
+    let full = Builder::new().fuzz(racy);
+    assert_eq!(full.executions, first.executions + rest.executions);
+}
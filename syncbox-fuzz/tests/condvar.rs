@@ -31,7 +31,7 @@ fn fuzz_condvar() {
         }
     }
 
-    syncbox_fuzz::fuzz(|| {
+    let test = || {
         let inc = Arc::new(Inc::new());
 
         for _ in 0..1 {
@@ -49,5 +49,11 @@ fn fuzz_condvar() {
 
             guard = inc.condvar.wait(guard).unwrap();
         }
-    });
+    };
+
+    let mut fuzz = syncbox_fuzz::fuzz::Builder::new();
+    assert_eq!(10, fuzz.fuzz(test).executions);
+
+    fuzz.sleep_sets = true;
+    assert_eq!(9, fuzz.fuzz(test).executions);
 }
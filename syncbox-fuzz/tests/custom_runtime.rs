@@ -0,0 +1,32 @@
+extern crate generator;
+extern crate syncbox_fuzz;
+
+mod support;
+
+use support::{builder, new_coroutines, SWITCHES};
+
+use syncbox_fuzz::fuzz::Runtime;
+use syncbox_fuzz::sync::Mutex;
+use syncbox_fuzz::thread;
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+#[test]
+fn switches_through_scheduler() {
+    SWITCHES.store(0, SeqCst);
+
+    let report = builder(Runtime::Custom(new_coroutines)).fuzz(|| {
+        let num = Arc::new(Mutex::new(0));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || *num.lock().unwrap() += 1)
+        };
+
+        *num.lock().unwrap() += 1;
+        th.join().unwrap();
+    });
+
+    assert!(SWITCHES.load(SeqCst) > report.executions);
+}
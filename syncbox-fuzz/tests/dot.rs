@@ -0,0 +1,136 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::Mutex;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::env;
+use std::fs;
+use std::panic;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// Fuzz `f`, which is expected to fail, and return the written graph
+fn failing_graph<F>(name: &str, f: F) -> String
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let file = env::temp_dir().join(format!("syncbox-fuzz-{}-{}.dot", name, process::id()));
+    let _ = fs::remove_file(&file);
+
+    let mut builder = Builder::new();
+    builder.dot_file(file.to_str().unwrap());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.fuzz(f);
+    }));
+
+    assert!(res.is_err());
+
+    let graph = fs::read_to_string(&file).unwrap();
+    fs::remove_file(&file).unwrap();
+    graph
+}
+
+#[test]
+fn graph_of_failing_execution() {
+    let graph = failing_graph("relaxed", || {
+        let data = Arc::new(AtomicUsize::new(0));
+        let flag = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let (data, flag) = (data.clone(), flag.clone());
+
+            thread::spawn(move || {
+                data.store(1, Relaxed);
+                flag.store(1, Relaxed);
+            })
+        };
+
+        if flag.load(Relaxed) == 1 {
+            assert_eq!(1, data.load(Relaxed));
+        }
+
+        th.join().unwrap();
+    });
+
+    assert!(graph.starts_with("digraph execution {"));
+    assert!(graph.contains("subgraph cluster_1"));
+    assert!(graph.contains("load(Relaxed) = 0"));
+    assert!(graph.contains("label=\"rf\""));
+    assert!(graph.contains("label=\"spawn\""));
+}
+
+#[test]
+fn synchronization_edges() {
+    let graph = failing_graph("sync", || {
+        let num = Arc::new(Mutex::new(0));
+        let flag = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let (num, flag) = (num.clone(), flag.clone());
+
+            thread::spawn(move || {
+                *num.lock().unwrap() += 1;
+                flag.store(1, Release);
+            })
+        };
+
+        *num.lock().unwrap() += 1;
+
+        // Loading before the join, the release is not implied by the join
+        if 1 == flag.load(Acquire) {
+            th.join().unwrap();
+            panic!("done");
+        }
+
+        th.join().unwrap();
+    });
+
+    assert!(graph.contains("mutex"));
+    assert!(graph.contains("rf, sw"));
+    assert!(graph.contains("label=\"join"));
+    assert!(graph.contains("label=\"mutex"));
+}
+
+#[test]
+fn one_synchronization_edge_per_release() {
+    let graph = failing_graph("release-sequence", || {
+        let flag = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let flag = flag.clone();
+
+            thread::spawn(move || {
+                for i in 1..4 {
+                    flag.store(i, Release);
+                }
+            })
+        };
+
+        // Only the last store synchronizes, and only with the first load
+        if flag.load(Acquire) == 3 {
+            flag.load(Acquire);
+            panic!("done");
+        }
+
+        th.join().unwrap();
+    });
+
+    assert_eq!(1, graph.matches("sw").count(), "{}", graph);
+}
+
+#[test]
+#[should_panic(expected = "original failure")]
+fn write_error_keeps_failure() {
+    let file = env::temp_dir()
+        .join("syncbox-fuzz-missing-dir")
+        .join("graph.dot");
+
+    let mut builder = Builder::new();
+    builder.dot_file(file.to_str().unwrap());
+
+    builder.fuzz(|| panic!("original failure"));
+}
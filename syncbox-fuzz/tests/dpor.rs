@@ -0,0 +1,152 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::{Builder, Dpor};
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering::SeqCst;
+
+fn stores_and_loads() {
+    let x = Arc::new(AtomicUsize::new(0));
+    let y = Arc::new(AtomicUsize::new(0));
+
+    let th1 = {
+        let x = x.clone();
+        thread::spawn(move || x.store(1, SeqCst))
+    };
+
+    let th2 = {
+        let y = y.clone();
+        thread::spawn(move || y.store(1, SeqCst))
+    };
+
+    let th3 = {
+        let (x, y) = (x.clone(), y.clone());
+        thread::spawn(move || {
+            x.load(SeqCst);
+            y.load(SeqCst);
+        })
+    };
+
+    th1.join().unwrap();
+    th2.join().unwrap();
+    th3.join().unwrap();
+}
+
+fn optimal() -> Builder {
+    let mut builder = Builder::new();
+    builder.dpor = Dpor::Optimal;
+    builder
+}
+
+fn outcomes(builder: &Builder) -> usize {
+    let outcomes = Arc::new(Mutex::new(HashSet::new()));
+
+    {
+        let outcomes = outcomes.clone();
+
+        builder.fuzz(move || {
+            let x = Arc::new(AtomicUsize::new(0));
+            let y = Arc::new(AtomicUsize::new(0));
+
+            let th1 = {
+                let x = x.clone();
+                thread::spawn(move || x.store(1, SeqCst))
+            };
+
+            let th2 = {
+                let y = y.clone();
+                thread::spawn(move || y.store(1, SeqCst))
+            };
+
+            let a = x.load(SeqCst);
+            let b = y.load(SeqCst);
+
+            th1.join().unwrap();
+            th2.join().unwrap();
+
+            outcomes.lock().unwrap().insert((a, b));
+        });
+    }
+
+    let outcomes = outcomes.lock().unwrap();
+    outcomes.len()
+}
+
+#[test]
+fn optimal_finds_all_outcomes() {
+    assert_eq!(4, outcomes(&optimal()));
+}
+
+#[test]
+fn optimal_finds_all_outcomes_with_workers() {
+    let mut builder = optimal();
+    builder.workers(4);
+
+    assert_eq!(4, outcomes(&builder));
+}
+
+#[test]
+fn optimal_resumes_from_checkpoint() {
+    let file = env::temp_dir().join(format!("syncbox-fuzz-dpor-{}.json", process::id()));
+    let _ = fs::remove_file(&file);
+
+    let full = optimal().fuzz(stores_and_loads);
+
+    let mut builder = optimal();
+    builder.checkpoint_file = Some(file.clone());
+    builder.max_iterations(2);
+
+    let first = builder.fuzz(stores_and_loads);
+    assert!(!first.complete);
+
+    builder.max_iterations = None;
+
+    // The wakeup trees of the remaining branch points are restored
+    let rest = builder.fuzz(stores_and_loads);
+    assert!(rest.complete);
+
+    assert_eq!(full.executions, first.executions + rest.executions);
+
+    fs::remove_file(&file).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "lost update")]
+fn optimal_finds_lost_update() {
+    optimal().fuzz(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2).map(|_| {
+            let num = num.clone();
+            thread::spawn(move || {
+                let curr = num.load(SeqCst);
+                num.store(curr + 1, SeqCst);
+            })
+        }).collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        assert_eq!(2, num.load(SeqCst), "lost update");
+    });
+}
+
+#[test]
+fn execution_counts() {
+    let mut classic = Builder::new();
+    classic.sleep_sets = true;
+
+    let classic = classic.fuzz(stores_and_loads);
+    let optimal = optimal().fuzz(stores_and_loads);
+
+    assert_eq!(28, classic.executions);
+    assert_eq!(17, optimal.executions);
+    assert!(optimal.executions < classic.executions);
+}
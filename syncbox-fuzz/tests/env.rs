@@ -0,0 +1,67 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::{Builder, Runtime};
+
+use std::env;
+use std::path::PathBuf;
+
+// Environment variables are shared by the whole process, so everything is
+// checked from a single test.
+#[test]
+fn builder_reads_env() {
+    env::set_var("SYNCBOX_FUZZ_MAX_THREADS", "3");
+    env::set_var("SYNCBOX_FUZZ_CHECKPOINT_FILE", "checkpoint.json");
+    env::set_var("SYNCBOX_FUZZ_CHECKPOINT_INTERVAL", "10");
+    env::set_var("SYNCBOX_FUZZ_RUNTIME", "thread");
+    env::set_var("SYNCBOX_FUZZ_LOG", "1");
+    env::set_var("SYNCBOX_FUZZ_MAX_PREEMPTIONS", "2");
+    env::set_var("SYNCBOX_FUZZ_REPLAY_FILE", "failing.json");
+
+    let builder = Builder::new();
+
+    assert_eq!(3, builder.max_threads);
+    assert_eq!(Some(PathBuf::from("checkpoint.json")), builder.checkpoint_file);
+    assert_eq!(10, builder.checkpoint_interval);
+    assert!(match builder.runtime { Runtime::Thread => true, _ => false });
+    assert!(builder.log);
+    assert_eq!(Some(2), builder.max_preemptions);
+    assert_eq!(Some(PathBuf::from("failing.json")), builder.replay_file);
+
+    // The environment takes precedence over values set in code
+    let mut builder = Builder::new();
+    builder.checkpoint_interval = 1_000;
+    builder.apply_env();
+
+    assert_eq!(10, builder.checkpoint_interval);
+
+    for name in &[
+        "SYNCBOX_FUZZ_MAX_THREADS",
+        "SYNCBOX_FUZZ_CHECKPOINT_FILE",
+        "SYNCBOX_FUZZ_CHECKPOINT_INTERVAL",
+        "SYNCBOX_FUZZ_RUNTIME",
+        "SYNCBOX_FUZZ_LOG",
+        "SYNCBOX_FUZZ_MAX_PREEMPTIONS",
+        "SYNCBOX_FUZZ_REPLAY_FILE",
+    ] {
+        env::remove_var(name);
+    }
+
+    let builder = Builder::new();
+
+    assert_eq!(None, builder.checkpoint_file);
+    assert!(!builder.log);
+    assert_eq!(None, builder.max_preemptions);
+    assert_eq!(None, builder.replay_file);
+
+    let mut builder = Builder::new();
+    builder.checkpoint_interval = 1_000;
+    builder.apply_env();
+
+    assert_eq!(1_000, builder.checkpoint_interval);
+
+    env::set_var("SYNCBOX_FUZZ_RUNTIME", "bogus");
+    let res = ::std::panic::catch_unwind(Builder::new);
+    env::remove_var("SYNCBOX_FUZZ_RUNTIME");
+
+    assert!(res.is_err());
+}
@@ -0,0 +1,114 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::Ordering::{Acquire, Release, Relaxed};
+use std::task::{Context, Poll};
+
+struct WaitFor {
+    num: Arc<AtomicUsize>,
+    spawned: bool,
+}
+
+impl Future for WaitFor {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if !self.spawned {
+            let num = self.num.clone();
+            let waker = cx.waker().clone();
+
+            thread::spawn(move || {
+                num.store(1, Release);
+                waker.wake();
+            });
+
+            self.spawned = true;
+        }
+
+        if 1 == self.num.load(Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn fuzz_valid() {
+    Builder::new().block_on(|| {
+        WaitFor {
+            num: Arc::new(AtomicUsize::new(0)),
+            spawned: false,
+        }
+    });
+}
+
+#[test]
+fn wake_by_ref_self() {
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    Builder::new().block_on(|| YieldOnce(false));
+}
+
+#[test]
+fn spawn_futures() {
+    struct Inc(Arc<AtomicUsize>);
+
+    impl Future for Inc {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context) -> Poll<()> {
+            self.0.fetch_add(1, Relaxed);
+            Poll::Ready(())
+        }
+    }
+
+    syncbox_fuzz::fuzz(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..2 {
+            syncbox_fuzz::future::spawn(Inc(num.clone()));
+        }
+
+        syncbox_fuzz::future::block_on(WaitFor {
+            num: Arc::new(AtomicUsize::new(0)),
+            spawned: false,
+        });
+    });
+}
+
+#[test]
+#[should_panic(expected = "lost wakeup")]
+fn never_woken() {
+    struct Pending;
+
+    impl Future for Pending {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context) -> Poll<()> {
+            Poll::Pending
+        }
+    }
+
+    Builder::new().block_on(|| Pending);
+}
@@ -3,6 +3,7 @@
 extern crate futures;
 extern crate syncbox_fuzz;
 
+use syncbox_fuzz::fuzz::Builder;
 use syncbox_fuzz::fuzz_future;
 use syncbox_fuzz::sync::atomic::AtomicUsize;
 use syncbox_fuzz::thread;
@@ -21,7 +22,7 @@ use std::sync::atomic::Ordering::Relaxed;
 
 #[test]
 fn fuzz_valid() {
-    fuzz_future(|| {
+    let test = || {
         lazy(|| {
             let num = Arc::new(AtomicUsize::new(0));
             let task = task::current();
@@ -47,5 +48,146 @@ fn fuzz_valid() {
                 }
             })
         })
+    };
+
+    let mut fuzz = Builder::new();
+    assert_eq!(3, fuzz.fuzz_future(test).executions);
+
+    fuzz.sleep_sets = true;
+    assert_eq!(3, fuzz.fuzz_future(test).executions);
+}
+
+#[test]
+fn executor_valid() {
+    use syncbox_fuzz::futures::{AtomicTask, Executor};
+
+    let test = || {
+        let chan = Arc::new((AtomicUsize::new(0), AtomicTask::new()));
+        let mut executor = Executor::new();
+
+        {
+            let chan = chan.clone();
+
+            executor.spawn(poll_fn(move || {
+                chan.1.register();
+
+                if 2 == chan.0.load(Relaxed) {
+                    Ok(Async::Ready(()))
+                } else {
+                    Ok(Async::NotReady)
+                }
+            }));
+        }
+
+        executor.spawn(lazy({
+            let chan = chan.clone();
+
+            move || {
+                chan.0.fetch_add(1, Relaxed);
+                chan.1.notify();
+                Ok(())
+            }
+        }));
+
+        thread::spawn(move || {
+            chan.0.fetch_add(1, Relaxed);
+            chan.1.notify();
+        });
+
+        executor.run();
+    };
+
+    let mut fuzz = Builder::new();
+    assert_eq!(17, fuzz.fuzz(test).executions);
+
+    fuzz.sleep_sets = true;
+    assert_eq!(17, fuzz.fuzz(test).executions);
+}
+
+#[test]
+#[should_panic(expected = "polled after the store")]
+fn executor_explores_poll_order() {
+    use syncbox_fuzz::futures::Executor;
+
+    syncbox_fuzz::fuzz(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let mut executor = Executor::new();
+
+        {
+            let num = num.clone();
+
+            executor.spawn(lazy(move || {
+                assert_eq!(0, num.load(Relaxed), "polled after the store");
+                Ok(())
+            }));
+        }
+
+        executor.spawn(lazy(move || {
+            num.store(1, Relaxed);
+            Ok(())
+        }));
+
+        executor.run();
+    });
+}
+
+#[test]
+#[should_panic(expected = "lost wakeup")]
+fn never_notified() {
+    fuzz_future(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        thread::spawn({
+            let num = num.clone();
+            move || num.store(1, Relaxed)
+        });
+
+        poll_fn(move || {
+            if 1 == num.load(Relaxed) {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            }
+        })
+    });
+}
+
+#[test]
+#[should_panic(expected = "lost wakeup: the future on thread 0 is parked")]
+fn own_task_handle_is_not_a_wakeup() {
+    fuzz_future(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let mut handles = vec![];
+
+        thread::spawn({
+            let num = num.clone();
+            move || num.store(1, Relaxed)
+        });
+
+        poll_fn(move || {
+            // The handle is never handed to the other thread
+            handles.push(task::current());
+
+            if 1 == num.load(Relaxed) {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            }
+        })
+    });
+}
+
+#[test]
+#[should_panic(expected = "lost wakeup: task 1 of the executor on thread 0 is parked")]
+fn executor_names_lost_task() {
+    use syncbox_fuzz::futures::Executor;
+
+    syncbox_fuzz::fuzz(|| {
+        let mut executor = Executor::new();
+
+        executor.spawn(lazy(|| Ok(())));
+        executor.spawn(poll_fn(|| Ok(Async::NotReady)));
+
+        executor.run();
     });
 }
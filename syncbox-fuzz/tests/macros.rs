@@ -0,0 +1,99 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+#[syncbox_fuzz::test]
+fn plain() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let th = {
+        let num = num.clone();
+        thread::spawn(move || num.fetch_add(1, SeqCst))
+    };
+
+    num.fetch_add(1, SeqCst);
+    th.join().unwrap();
+
+    assert_eq!(2, num.load(SeqCst));
+}
+
+#[syncbox_fuzz::test(max_threads = 3, runtime = "thread", max_preemptions = 0)]
+fn with_options() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let th = {
+        let num = num.clone();
+        thread::spawn(move || {
+            let curr = num.load(SeqCst);
+            num.store(curr + 1, SeqCst);
+        })
+    };
+
+    let curr = num.load(SeqCst);
+    num.store(curr + 1, SeqCst);
+    th.join().unwrap();
+
+    // Without preemptions, the increments do not race
+    assert_eq!(2, num.load(SeqCst));
+}
+
+#[syncbox_fuzz::test]
+#[should_panic(expected = "lost update")]
+fn finds_race() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let th = {
+        let num = num.clone();
+        thread::spawn(move || {
+            let curr = num.load(SeqCst);
+            num.store(curr + 1, SeqCst);
+        })
+    };
+
+    let curr = num.load(SeqCst);
+    num.store(curr + 1, SeqCst);
+    th.join().unwrap();
+
+    assert_eq!(2, num.load(SeqCst), "lost update");
+}
+
+#[cfg(feature = "futures")]
+mod futures {
+    extern crate futures;
+
+    use self::futures::{Async, Future};
+    use self::futures::future::poll_fn;
+    use syncbox_fuzz::futures::task;
+    use syncbox_fuzz::sync::atomic::AtomicUsize;
+    use syncbox_fuzz::thread;
+
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering::SeqCst;
+
+    #[syncbox_fuzz::test(max_threads = 2)]
+    fn returns_future() -> impl Future<Item = (), Error = ()> {
+        let num = Arc::new(AtomicUsize::new(0));
+        let task = task::current();
+
+        {
+            let num = num.clone();
+
+            thread::spawn(move || {
+                num.store(1, SeqCst);
+                task.notify();
+            });
+        }
+
+        poll_fn(move || {
+            if 1 == num.load(SeqCst) {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            }
+        })
+    }
+}
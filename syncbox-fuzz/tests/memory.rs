@@ -0,0 +1,83 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Every store allocates the causality of the write in the arena
+fn many_stores() {
+    let num = AtomicUsize::new(0);
+
+    for i in 0..1_000 {
+        num.store(i, SeqCst);
+    }
+}
+
+/// Every load replaces the last access of the atomic, dropping the previous
+/// one
+fn many_loads() {
+    let num = AtomicUsize::new(0);
+
+    for _ in 0..1_000 {
+        num.load(SeqCst);
+    }
+}
+
+/// Every execution leaks the metadata of a few atomics. A single execution
+/// fits in the arena of `small`, but not the four of them.
+fn leaky() {
+    for _ in 0..12 {
+        mem::forget(AtomicUsize::new(0));
+    }
+
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let th = {
+        let num = num.clone();
+        thread::spawn(move || num.fetch_add(1, SeqCst))
+    };
+
+    num.fetch_add(1, SeqCst);
+    th.join().unwrap();
+}
+
+fn small() -> Builder {
+    let mut builder = Builder::new();
+    builder.max_memory = 4096;
+    builder
+}
+
+#[test]
+#[should_panic(expected = "arena exhausted")]
+fn exceeding_max_memory_fails() {
+    small().fuzz(many_stores);
+}
+
+#[test]
+fn grow_memory_maps_more_chunks() {
+    let mut builder = small();
+    builder.grow_memory = true;
+
+    let report = builder.fuzz(many_stores);
+
+    assert_eq!(1, report.executions);
+    assert!(report.peak_memory > 4096);
+}
+
+#[test]
+fn dropped_metadata_is_reused() {
+    let report = small().fuzz(many_loads);
+
+    assert_eq!(1, report.executions);
+    assert!(report.peak_memory <= 4096);
+}
+
+#[test]
+#[should_panic(expected = "arena exhausted")]
+fn leaked_metadata_is_not_remapped() {
+    small().fuzz(leaky);
+}
@@ -13,7 +13,7 @@ fn mutex_enforces_mutal_exclusion() {
     fuzz.log = true;
     fuzz.checkpoint_interval = 1;
 
-    fuzz.fuzz(|| {
+    let test = || {
         let data = Arc::new((Mutex::new(0), AtomicUsize::new(0)));
 
         let ths: Vec<_> = (0..2).map(|_| {
@@ -35,7 +35,12 @@ fn mutex_enforces_mutal_exclusion() {
         let locked = data.0.lock().unwrap();
 
         assert_eq!(*locked, data.1.load(SeqCst));
-    });
+    };
+
+    assert_eq!(4, fuzz.fuzz(test).executions);
+
+    fuzz.sleep_sets = true;
+    assert_eq!(4, fuzz.fuzz(test).executions);
 }
 
 #[test]
@@ -44,7 +49,7 @@ fn mutex_establishes_seq_cst() {
     fuzz.log = true;
     fuzz.checkpoint_interval = 1;
 
-    fuzz.fuzz(|| {
+    let test = || {
         struct Data {
             cell: CausalCell<usize>,
             flag: Mutex<bool>,
@@ -70,5 +75,10 @@ fn mutex_establishes_seq_cst() {
             let v = unsafe { data.cell.with(|v| *v) };
             assert_eq!(v, 1);
         }
-    });
+    };
+
+    assert_eq!(3, fuzz.fuzz(test).executions);
+
+    fuzz.sleep_sets = true;
+    assert_eq!(3, fuzz.fuzz(test).executions);
 }
@@ -0,0 +1,133 @@
+extern crate serde_json;
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use serde_json::Value;
+use std::env;
+use std::fs::{self, File};
+use std::panic;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize as StdAtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Fuzz `f`, which is expected to fail, and return the panic message
+fn failure<F>(f: F) -> String
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        Builder::new().fuzz(f);
+    }));
+
+    *res.unwrap_err().downcast::<String>().unwrap()
+}
+
+#[test]
+fn operation_differs_on_replay() {
+    let runs = StdAtomicUsize::new(0);
+
+    let message = failure(move || {
+        let first = runs.fetch_add(1, SeqCst) == 0;
+        let num = Arc::new(AtomicUsize::new(0));
+
+        // Only the first execution loads from the atomic before the race
+        if first {
+            num.load(SeqCst);
+        }
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || { num.fetch_add(1, SeqCst); })
+        };
+
+        num.fetch_add(2, SeqCst);
+        th.join().unwrap();
+    });
+
+    assert!(message.starts_with("non-deterministic test: expected atomic load on object 0 at step "), "{}", message);
+    assert!(message.contains("recorded:\n  0: schedule\n"), "{}", message);
+    assert!(message.contains("replayed:\n"), "{}", message);
+}
+
+#[test]
+fn thread_not_runnable_on_replay() {
+    let runs = StdAtomicUsize::new(0);
+
+    let message = failure(move || {
+        // Only the first execution races
+        if runs.fetch_add(1, SeqCst) != 0 {
+            return;
+        }
+
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || { num.fetch_add(1, SeqCst); })
+        };
+
+        num.fetch_add(2, SeqCst);
+        th.join().unwrap();
+    });
+
+    assert!(message.starts_with("non-deterministic test: expected schedule of thread 1 at step "), "{}", message);
+    assert!(message.contains(", got thread 1 not runnable\n"), "{}", message);
+}
+
+/// Spawn a thread doing `adds` increments and race it with the main thread.
+/// Returns the value the main thread's increment read.
+fn adds(adds: usize) -> usize {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let th = {
+        let num = num.clone();
+        thread::spawn(move || {
+            for _ in 0..adds {
+                num.fetch_add(1, SeqCst);
+            }
+        })
+    };
+
+    let prev = num.fetch_add(1, SeqCst);
+    th.join().unwrap();
+    prev
+}
+
+#[test]
+fn diverges_from_path_without_kinds() {
+    let file = env::temp_dir().join(format!("syncbox-fuzz-kinds-{}.json", process::id()));
+
+    // The failing path, in which the spawned thread runs first, is written
+    // to the checkpoint file.
+    let mut builder = Builder::new();
+    builder.checkpoint_file = Some(file.clone());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.fuzz(|| assert_eq!(0, adds(2)));
+    }));
+
+    assert!(res.is_err());
+
+    // Paths recorded before branch kinds were
+    let mut path: Value = serde_json::from_reader(File::open(&file).unwrap()).unwrap();
+    path.as_object_mut().unwrap().remove("kinds").unwrap();
+    serde_json::to_writer(File::create(&file).unwrap(), &path).unwrap();
+
+    let mut builder = Builder::new();
+    builder.replay_file = Some(file.clone());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.fuzz(|| { adds(1); });
+    }));
+
+    fs::remove_file(&file).unwrap();
+
+    let message = *res.unwrap_err().downcast::<String>().unwrap();
+
+    assert!(message.starts_with("non-deterministic test: expected schedule of thread 1 at step "), "{}", message);
+    assert!(message.contains("recorded:\nreplayed:\n  3: thread 1 not runnable\n"), "{}", message);
+}
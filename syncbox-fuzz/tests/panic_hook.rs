@@ -0,0 +1,74 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::env;
+use std::fs;
+use std::panic;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic;
+use std::sync::atomic::Ordering::SeqCst;
+
+// Panic hooks are global, so this file holds a single test
+
+static CALLS: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+
+fn lost_update() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+
+        thread::spawn(move || {
+            let curr = num.load(SeqCst);
+            num.store(curr + 1, SeqCst);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(2, num.load(SeqCst), "lost update");
+}
+
+/// Set a hook counting panics, shrink a failure, then panic once more.
+/// Returns the number of panics that reached the hook.
+fn calls_after_shrinking() -> usize {
+    let file = env::temp_dir().join(format!("syncbox-fuzz-panic-hook-{}.json", process::id()));
+
+    CALLS.store(0, SeqCst);
+
+    panic::set_hook(Box::new(|_| {
+        CALLS.fetch_add(1, SeqCst);
+    }));
+
+    let mut builder = Builder::new();
+    builder.shrink_file(file.to_str().unwrap());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.fuzz(lost_update);
+    }));
+
+    assert!(res.is_err());
+    fs::remove_file(&file).unwrap();
+
+    let _ = panic::catch_unwind(|| panic!("after shrinking"));
+
+    CALLS.load(SeqCst)
+}
+
+#[test]
+fn shrinking_restores_panic_hook() {
+    // The failure and the panic after shrinking reach the hook, the replayed
+    // candidates do not
+    assert_eq!(2, calls_after_shrinking());
+
+    // A hook set after shrinking is silenced by the next shrink as well
+    assert_eq!(2, calls_after_shrinking());
+
+    drop(panic::take_hook());
+}
@@ -0,0 +1,107 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Increments that only race when a thread is preempted between the load and
+/// the store.
+fn lost_update() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+        thread::spawn(move || {
+            let curr = num.load(SeqCst);
+            num.store(curr + 1, SeqCst);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(2, num.load(SeqCst), "lost update");
+}
+
+#[test]
+fn no_preemptions_hides_race() {
+    let mut builder = Builder::new();
+    builder.max_preemptions = Some(0);
+
+    builder.fuzz(lost_update);
+}
+
+#[test]
+#[should_panic(expected = "lost update")]
+fn one_preemption_finds_race() {
+    let mut builder = Builder::new();
+    builder.max_preemptions = Some(1);
+
+    builder.fuzz(lost_update);
+}
+
+#[test]
+fn shards_respect_bound() {
+    for i in 0..2 {
+        let mut builder = Builder::new();
+        builder.max_preemptions = Some(0);
+        builder.shard(i, 2);
+
+        builder.fuzz(lost_update);
+    }
+}
+
+fn increments() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+        thread::spawn(move || {
+            num.fetch_add(1, SeqCst);
+            num.fetch_add(1, SeqCst);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(4, num.load(SeqCst));
+}
+
+#[test]
+fn bound_reduces_executions() {
+    let full = Builder::new().fuzz(increments);
+
+    let mut builder = Builder::new();
+    builder.max_preemptions = Some(0);
+    let bounded = builder.fuzz(increments);
+
+    assert!(bounded.executions < full.executions);
+}
+
+#[test]
+fn replay_runs_single_execution() {
+    let file = env::temp_dir().join(format!("syncbox-fuzz-replay-{}.json", process::id()));
+    let _ = fs::remove_file(&file);
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file = Some(file.clone());
+    builder.max_iterations(2);
+    builder.fuzz(increments);
+
+    let mut builder = Builder::new();
+    builder.replay_file = Some(file.clone());
+
+    let report = builder.fuzz(increments);
+    assert_eq!(1, report.executions);
+
+    fs::remove_file(&file).unwrap();
+}
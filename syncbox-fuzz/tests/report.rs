@@ -0,0 +1,73 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::Barrier;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering::Relaxed;
+
+#[test]
+fn report_single_thread() {
+    let report = syncbox_fuzz::fuzz(|| {
+        let num = AtomicUsize::new(0);
+        num.store(1, Relaxed);
+    });
+
+    assert_eq!(1, report.executions);
+    assert_eq!(1, report.threads);
+    assert_eq!(1, report.objects);
+    assert!(report.peak_memory > 0);
+}
+
+#[test]
+fn report_counts_branches() {
+    let body = || {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || num.store(1, Relaxed))
+        };
+
+        num.load(Relaxed);
+        th.join().unwrap();
+    };
+
+    let report = syncbox_fuzz::fuzz(body);
+
+    assert!(report.executions > 1);
+    assert!(report.schedule_branches > 0);
+    assert!(report.write_branches > 0);
+    assert!(report.max_depth > 0);
+    assert_eq!(2 * report.executions, report.threads);
+
+    let mut builder = Builder::new();
+    builder.workers(2);
+
+    let parallel = builder.fuzz(body);
+
+    assert!(parallel.executions > 1);
+    assert_eq!(report.max_depth, parallel.max_depth);
+}
+
+#[test]
+fn report_counts_branches_by_kind() {
+    let report = syncbox_fuzz::fuzz(|| {
+        let barrier = Arc::new(Barrier::new(2));
+
+        let th = {
+            let barrier = barrier.clone();
+            thread::spawn(move || barrier.wait())
+        };
+
+        barrier.wait();
+        th.join().unwrap();
+    });
+
+    assert!(report.leader_branches > 0);
+    assert_eq!(0, report.write_branches);
+    assert_eq!(0, report.timeout_branches);
+    assert_eq!(0, report.poll_branches);
+}
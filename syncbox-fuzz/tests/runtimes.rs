@@ -0,0 +1,84 @@
+extern crate generator;
+extern crate syncbox_fuzz;
+
+mod support;
+
+use support::{builder, new_coroutines};
+
+use syncbox_fuzz::fuzz::Runtime;
+use syncbox_fuzz::sync::Mutex;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Runtimes checked against the generator runtime
+fn runtimes() -> Vec<(&'static str, Runtime)> {
+    let mut runtimes = vec![
+        ("thread", Runtime::Thread),
+        ("custom", Runtime::Custom(new_coroutines)),
+    ];
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    runtimes.push(("ucontext", Runtime::Ucontext));
+
+    runtimes
+}
+
+fn increments() {
+    let num = Arc::new(Mutex::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+        thread::spawn(move || {
+            *num.lock().unwrap() += 1;
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(2, *num.lock().unwrap());
+}
+
+fn lost_update() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+        thread::spawn(move || {
+            let v = num.load(SeqCst);
+            num.store(v + 1, SeqCst);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(2, num.load(SeqCst));
+}
+
+#[test]
+fn explores_same_executions_as_generator() {
+    let generator = builder(Runtime::Generator).fuzz(increments);
+
+    for (name, runtime) in runtimes() {
+        let report = builder(runtime).fuzz(increments);
+        assert_eq!(generator.executions, report.executions, "runtime = {}", name);
+    }
+}
+
+#[test]
+fn finds_lost_update() {
+    for (name, runtime) in runtimes() {
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            builder(runtime).fuzz(lost_update);
+        }));
+
+        assert!(res.is_err(), "runtime = {}", name);
+    }
+}
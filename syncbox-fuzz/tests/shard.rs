@@ -0,0 +1,65 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+/// Explores the test and returns the set of observed outcomes
+fn outcomes(builder: &Builder) -> BTreeSet<(usize, usize)> {
+    let seen = Arc::new(Mutex::new(BTreeSet::new()));
+
+    {
+        let seen = seen.clone();
+
+        builder.fuzz(move || {
+            let num = Arc::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+
+            let th = {
+                let num = num.clone();
+
+                thread::spawn(move || {
+                    num.0.store(1, Relaxed);
+                    num.1.store(1, Relaxed);
+                })
+            };
+
+            let a = num.1.load(Relaxed);
+            let b = num.0.load(Relaxed);
+
+            th.join().unwrap();
+
+            seen.lock().unwrap().insert((a, b));
+            assert_eq!(1, num.0.load(SeqCst));
+        });
+    }
+
+    let seen = seen.lock().unwrap();
+    seen.clone()
+}
+
+#[test]
+fn shards_cover_tree() {
+    let expect = outcomes(&Builder::new());
+
+    let mut actual = BTreeSet::new();
+
+    for i in 0..3 {
+        let mut builder = Builder::new();
+        builder.shard(i, 3);
+        builder.shard_depth = 2;
+
+        actual.extend(outcomes(&builder));
+    }
+
+    assert_eq!(expect, actual);
+}
+
+#[test]
+#[should_panic(expected = "shard index out of bounds")]
+fn shard_index_out_of_bounds() {
+    Builder::new().shard(3, 3);
+}
@@ -0,0 +1,173 @@
+extern crate generator;
+extern crate serde_json;
+extern crate syncbox_fuzz;
+
+mod support;
+
+use support::{builder, new_coroutines};
+
+use syncbox_fuzz::fuzz::{Builder, Runtime};
+use syncbox_fuzz::scheduler::Scheduler;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use serde_json::Value;
+
+use std::env;
+use std::fs::{self, File};
+use std::panic;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic;
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+static SCHEDULERS: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+
+/// Fuzz `f`, which is expected to fail, and return the minimal path
+fn shrunk_path<F>(name: &str, f: F) -> (Value, PathBuf)
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    shrunk_path_with(name, Builder::new(), f)
+}
+
+fn shrunk_path_with<F>(name: &str, mut builder: Builder, f: F) -> (Value, PathBuf)
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let file = env::temp_dir().join(format!("syncbox-fuzz-shrink-{}-{}.json", name, process::id()));
+    let _ = fs::remove_file(&file);
+
+    builder.shrink_file(file.to_str().unwrap());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.fuzz(f);
+    }));
+
+    assert!(res.is_err());
+
+    let path = serde_json::from_reader(File::open(&file).unwrap()).unwrap();
+    (path, file)
+}
+
+/// Number of schedules that do not pick the thread that could keep running
+fn preemptions(path: &Value) -> usize {
+    path["schedules"].as_array().unwrap().iter()
+        .filter(|schedule| {
+            match schedule["current"].as_u64() {
+                Some(current) => schedule["threads"][current as usize] != "Active",
+                None => false,
+            }
+        })
+        .count()
+}
+
+fn counted_coroutines(max_threads: usize, stack_size: usize) -> Box<Scheduler> {
+    SCHEDULERS.fetch_add(1, SeqCst);
+    new_coroutines(max_threads, stack_size)
+}
+
+fn lost_update() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+
+        thread::spawn(move || {
+            let curr = num.load(SeqCst);
+            num.store(curr + 1, SeqCst);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(2, num.load(SeqCst), "lost update");
+}
+
+#[test]
+fn single_preemption() {
+    let (path, file) = shrunk_path("preemption", lost_update);
+
+    assert_eq!(1, preemptions(&path));
+
+    // The minimal path replays to the same failure
+    let mut builder = Builder::new();
+    builder.replay_file = Some(file.clone());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.fuzz(lost_update);
+    }));
+
+    fs::remove_file(&file).unwrap();
+
+    let err = res.unwrap_err();
+    assert!(err.downcast_ref::<String>().unwrap().contains("lost update"));
+}
+
+#[test]
+fn single_preemption_with_thread_runtime() {
+    let (path, file) = shrunk_path_with("thread", builder(Runtime::Thread), lost_update);
+    fs::remove_file(&file).unwrap();
+
+    assert_eq!(1, preemptions(&path));
+}
+
+#[test]
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn single_preemption_with_ucontext_runtime() {
+    let (path, file) = shrunk_path_with("ucontext", builder(Runtime::Ucontext), lost_update);
+    fs::remove_file(&file).unwrap();
+
+    assert_eq!(1, preemptions(&path));
+}
+
+#[test]
+fn shrinking_reuses_the_scheduler() {
+    let builder = builder(Runtime::Custom(counted_coroutines));
+
+    let (path, file) = shrunk_path_with("custom", builder, lost_update);
+    fs::remove_file(&file).unwrap();
+
+    assert_eq!(1, preemptions(&path));
+
+    // One for fuzzing, one for replaying all the candidates
+    assert_eq!(2, SCHEDULERS.load(SeqCst));
+}
+
+#[test]
+fn no_stale_reads_needed_for_the_failure() {
+    let (path, file) = shrunk_path("stale", || {
+        let data = Arc::new(AtomicUsize::new(0));
+        let flag = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let (data, flag) = (data.clone(), flag.clone());
+
+            thread::spawn(move || {
+                data.store(1, Relaxed);
+                flag.store(1, Relaxed);
+            })
+        };
+
+        // Fails whenever the flag is seen, however stale the data is
+        if flag.load(Relaxed) == 1 {
+            panic!("flag set");
+        }
+
+        th.join().unwrap();
+    });
+
+    fs::remove_file(&file).unwrap();
+
+    assert_eq!(1, preemptions(&path));
+
+    // Seeing the flag needs the spawned thread to run first, the loads read
+    // the latest writes
+    for writes in path["writes"].as_array().unwrap() {
+        let writes = writes.as_array().unwrap();
+        assert!(writes.iter().all(|w| w.as_u64() <= writes[0].as_u64()));
+    }
+}
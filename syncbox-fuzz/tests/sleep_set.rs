@@ -0,0 +1,136 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::Mutex;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+struct Pair {
+    x: AtomicUsize,
+    y: AtomicUsize,
+}
+
+/// Number of executions explored with and without sleep sets
+fn executions<F>(f: F) -> (usize, usize)
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let f = Arc::new(f);
+
+    let run = |sleep_sets| {
+        let f = f.clone();
+
+        let mut builder = Builder::new();
+        builder.sleep_sets = sleep_sets;
+        builder.fuzz(move || f()).executions
+    };
+
+    (run(true), run(false))
+}
+
+/// Two writers to different atomics and a reader of both. The order of the
+/// two stores does not matter.
+fn writers_and_reader<F>(check: F)
+where
+    F: Fn(usize, usize) + Send + Sync + 'static,
+{
+    let check = Arc::new(check);
+
+    let pair = Arc::new(Pair {
+        x: AtomicUsize::new(0),
+        y: AtomicUsize::new(0),
+    });
+
+    let th1 = {
+        let pair = pair.clone();
+        thread::spawn(move || pair.x.store(1, SeqCst))
+    };
+
+    let th2 = {
+        let pair = pair.clone();
+        thread::spawn(move || pair.y.store(1, SeqCst))
+    };
+
+    let th3 = {
+        let pair = pair.clone();
+        let check = check.clone();
+
+        thread::spawn(move || {
+            let x = pair.x.load(SeqCst);
+            let y = pair.y.load(SeqCst);
+
+            check(x, y);
+        })
+    };
+
+    th1.join().unwrap();
+    th2.join().unwrap();
+    th3.join().unwrap();
+}
+
+fn increments() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+        thread::spawn(move || {
+            num.fetch_add(1, SeqCst);
+            num.fetch_add(1, SeqCst);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(4, num.load(SeqCst));
+}
+
+fn mutual_exclusion() {
+    let data = Arc::new((Mutex::new(0), AtomicUsize::new(0)));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let data = data.clone();
+
+        thread::spawn(move || {
+            let mut locked = data.0.lock().unwrap();
+
+            let prev = data.1.fetch_add(1, SeqCst);
+            assert_eq!(prev, *locked);
+            *locked += 1;
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    let locked = data.0.lock().unwrap();
+
+    assert_eq!(*locked, data.1.load(SeqCst));
+}
+
+#[test]
+fn independent_stores_are_not_reordered() {
+    assert_eq!((28, 35), executions(|| writers_and_reader(|_, _| {})));
+}
+
+#[test]
+fn dependent_operations_are_all_reordered() {
+    // Every operation races with the other thread, nothing falls asleep
+    assert_eq!((10, 10), executions(increments));
+    assert_eq!((4, 4), executions(mutual_exclusion));
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: !(x == 0 && y == 1)")]
+fn reordered_reads_are_still_found() {
+    Builder::new().fuzz(|| {
+        writers_and_reader(|x, y| {
+            assert!(!(x == 0 && y == 1));
+        });
+    });
+}
@@ -39,7 +39,7 @@ fn fuzz_valid() {
     fuzz.log = true;
     fuzz.checkpoint_interval = 1;
 
-    fuzz.fuzz(|| {
+    let test = || {
         let inc = Arc::new(Inc::new());
 
         let ths: Vec<_> = (0..2).map(|_| {
@@ -54,7 +54,12 @@ fn fuzz_valid() {
         }
 
         assert_eq!(2, inc.num.load(Relaxed));
-    });
+    };
+
+    assert_eq!(6, fuzz.fuzz(test).executions);
+
+    fuzz.sleep_sets = true;
+    assert_eq!(6, fuzz.fuzz(test).executions);
 }
 
 #[test]
@@ -0,0 +1,62 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::{Builder, Runtime};
+use syncbox_fuzz::thread;
+
+use std::env;
+use std::process::Command;
+
+/// Uses at least `depth` KiB of stack
+fn recurse(depth: usize) -> usize {
+    let buf = [depth as u8; 1024];
+
+    if depth == 0 {
+        return 0;
+    }
+
+    recurse(depth - 1) + buf[depth % buf.len()] as usize
+}
+
+fn generator(stack_size: usize) -> Builder {
+    let mut builder = Builder::new();
+    builder.runtime = Runtime::Generator;
+    builder.stack_size(stack_size);
+    builder
+}
+
+#[test]
+fn deep_recursion_with_large_stack() {
+    generator(32 << 20).fuzz(|| {
+        let th = thread::spawn(|| {
+            recurse(16 << 10);
+        });
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn overflow_names_model_thread() {
+    if env::var_os("STACK_OVERFLOW_CHILD").is_some() {
+        generator(64 << 10).fuzz(|| {
+            let th = thread::spawn(|| {
+                recurse(1 << 20);
+            });
+
+            th.join().unwrap();
+        });
+
+        return;
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args(&["overflow_names_model_thread", "--exact", "--nocapture"])
+        .env("STACK_OVERFLOW_CHILD", "1")
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("model thread stack overflow: thread 1"), "{}", stderr);
+}
@@ -0,0 +1,199 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::state::{self, State};
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering::SeqCst;
+
+fn cached() -> Builder {
+    let mut builder = Builder::new();
+    builder.cache_states = true;
+    builder
+}
+
+/// Both threads first attempt a CAS that fails. The order of the attempts does
+/// not change the program state.
+fn failed_cas_then_increment() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+        thread::spawn(move || {
+            num.compare_and_swap(5, 6, SeqCst);
+            num.fetch_add(1, SeqCst);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(2, num.load(SeqCst));
+}
+
+#[test]
+fn caching_prunes_revisited_states() {
+    let uncached = Builder::new().fuzz(failed_cas_then_increment);
+    let cached = cached().fuzz(failed_cas_then_increment);
+
+    assert!(cached.executions < uncached.executions,
+            "cached = {}; uncached = {}", cached.executions, uncached.executions);
+}
+
+#[test]
+#[should_panic(expected = "lost update")]
+fn caching_finds_lost_update() {
+    cached().fuzz(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2).map(|_| {
+            let num = num.clone();
+            thread::spawn(move || {
+                num.compare_and_swap(5, 6, SeqCst);
+                let v = num.load(SeqCst);
+                num.store(v + 1, SeqCst);
+            })
+        }).collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        assert_eq!(2, num.load(SeqCst), "lost update");
+    });
+}
+
+struct Log(Mutex<Vec<usize>>);
+
+impl State for Log {
+    fn hash_state(&self, mut hasher: &mut Hasher) {
+        self.0.lock().unwrap().hash(&mut hasher);
+    }
+}
+
+#[test]
+fn tracked_state_is_hashed() {
+    let outcomes = Arc::new(Mutex::new(HashSet::new()));
+
+    {
+        let outcomes = outcomes.clone();
+
+        cached().fuzz(move || {
+            let num = Arc::new(AtomicUsize::new(0));
+            let log = Arc::new(Log(Mutex::new(vec![])));
+            let first = Arc::new(Mutex::new(None));
+
+            state::track(&log);
+
+            let ths: Vec<_> = (0..2).map(|i| {
+                let num = num.clone();
+                let log = log.clone();
+                let first = first.clone();
+
+                thread::spawn(move || {
+                    num.compare_and_swap(5, 6, SeqCst);
+                    log.0.lock().unwrap().push(i);
+
+                    if 0 == num.fetch_add(1, SeqCst) {
+                        *first.lock().unwrap() = Some(i);
+                    }
+                })
+            }).collect();
+
+            for th in ths {
+                th.join().unwrap();
+            }
+
+            let log = log.0.lock().unwrap().clone();
+            let first = first.lock().unwrap().unwrap();
+
+            outcomes.lock().unwrap().insert((log, first));
+        });
+    }
+
+    let outcomes = outcomes.lock().unwrap();
+    assert_eq!(4, outcomes.len());
+}
+
+struct Results(Mutex<Vec<Vec<usize>>>);
+
+impl State for Results {
+    fn hash_state(&self, mut hasher: &mut Hasher) {
+        self.0.lock().unwrap().hash(&mut hasher);
+    }
+}
+
+/// Values observed by each thread across all explored executions
+fn outcomes(builder: Builder) -> HashSet<Vec<Vec<usize>>> {
+    let outcomes = Arc::new(Mutex::new(HashSet::new()));
+
+    {
+        let outcomes = outcomes.clone();
+
+        builder.fuzz(move || {
+            let x = Arc::new(AtomicUsize::new(0));
+            let y = Arc::new(AtomicUsize::new(0));
+            let z = Arc::new(AtomicUsize::new(0));
+            let results = Arc::new(Results(Mutex::new(vec![vec![]; 3])));
+
+            state::track(&results);
+
+            let push = |results: &Results, i: usize, v: usize| {
+                results.0.lock().unwrap()[i].push(v);
+            };
+
+            let th0 = {
+                let (x, z, results) = (x.clone(), z.clone(), results.clone());
+
+                thread::spawn(move || {
+                    push(&results, 0, x.compare_and_swap(5, 6, SeqCst));
+                    push(&results, 0, z.fetch_add(1, SeqCst));
+                })
+            };
+
+            let th1 = {
+                let (y, results) = (y.clone(), results.clone());
+
+                thread::spawn(move || {
+                    push(&results, 1, y.fetch_add(1, SeqCst));
+                    push(&results, 1, y.swap(5, SeqCst));
+                })
+            };
+
+            let th2 = {
+                let (x, y, results) = (x.clone(), y.clone(), results.clone());
+
+                thread::spawn(move || {
+                    push(&results, 2, y.fetch_add(1, SeqCst));
+                    push(&results, 2, x.fetch_add(1, SeqCst));
+                })
+            };
+
+            th0.join().unwrap();
+            th1.join().unwrap();
+            th2.join().unwrap();
+
+            let results = results.0.lock().unwrap().clone();
+            outcomes.lock().unwrap().insert(results);
+        });
+    }
+
+    let outcomes = outcomes.lock().unwrap();
+    outcomes.clone()
+}
+
+#[test]
+fn races_after_explored_state_are_reversed() {
+    // The failed CAS on `x` races with the increment of `x`, which the
+    // execution only reaches after revisiting an explored state.
+    let uncached = outcomes(Builder::new());
+    let cached = outcomes(cached());
+
+    assert_eq!(6, uncached.len());
+    assert_eq!(uncached, cached);
+}
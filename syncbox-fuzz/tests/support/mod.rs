@@ -0,0 +1,75 @@
+//! Fixtures shared by the runtime tests
+
+#![allow(dead_code)]
+
+use syncbox_fuzz::fuzz::{Builder, Runtime};
+use syncbox_fuzz::scheduler::{Execution, Scheduler, Task};
+
+use generator::{self, Generator, Gn};
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic;
+
+pub static SWITCHES: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+
+/// Runs model threads as generators, counting context switches
+struct Coroutines {
+    stack_size: usize,
+    execution: Cell<Option<Execution>>,
+    queued_spawn: RefCell<VecDeque<Task>>,
+}
+
+impl Scheduler for Coroutines {
+    fn run(&self, execution: Execution, f: Task) {
+        self.execution.set(Some(execution));
+
+        // Left over by an execution that panicked
+        self.queued_spawn.borrow_mut().clear();
+
+        let mut threads = vec![self.coroutine(f)];
+
+        while let Some(active) = execution.active_thread() {
+            threads[active].resume();
+
+            while let Some(task) = self.queued_spawn.borrow_mut().pop_front() {
+                threads.push(self.coroutine(task));
+            }
+        }
+    }
+
+    #[allow(deprecated)]
+    fn switch(&self) {
+        SWITCHES.fetch_add(1, SeqCst);
+        generator::yield_with(());
+    }
+
+    fn spawn(&self, f: Task) {
+        self.queued_spawn.borrow_mut().push_back(f);
+    }
+
+    fn with_execution(&self, f: &mut FnMut(Execution)) {
+        f(self.execution.get().unwrap());
+    }
+}
+
+impl Coroutines {
+    fn coroutine(&self, task: Task) -> Generator<'static, (), ()> {
+        Gn::new_opt(self.stack_size / 8, move || task.run())
+    }
+}
+
+pub fn new_coroutines(_max_threads: usize, stack_size: usize) -> Box<Scheduler> {
+    Box::new(Coroutines {
+        stack_size,
+        execution: Cell::new(None),
+        queued_spawn: RefCell::new(VecDeque::new()),
+    })
+}
+
+pub fn builder(runtime: Runtime) -> Builder {
+    let mut builder = Builder::new();
+    builder.runtime = runtime;
+    builder
+}
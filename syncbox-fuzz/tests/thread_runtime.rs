@@ -0,0 +1,103 @@
+extern crate generator;
+extern crate syncbox_fuzz;
+
+mod support;
+
+use support::builder;
+
+use syncbox_fuzz::fuzz::Runtime;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use std::env;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+#[test]
+fn spawned_thread_outlives_root() {
+    builder(Runtime::Thread).fuzz(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || {
+                num.fetch_add(1, SeqCst);
+            })
+        };
+
+        // The spawned thread is never joined, so it may outlive the root
+        // thread.
+        num.fetch_add(1, SeqCst);
+        drop(th);
+    });
+}
+
+#[test]
+#[should_panic(expected = "loaded before the store")]
+fn panic_in_spawned_thread() {
+    builder(Runtime::Thread).fuzz(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || {
+                assert_eq!(1, num.load(SeqCst), "loaded before the store");
+            })
+        };
+
+        num.store(1, SeqCst);
+        th.join().unwrap();
+    });
+}
+
+/// Number of OS threads in this process
+#[cfg(target_os = "linux")]
+fn os_threads() -> usize {
+    let status = fs::read_to_string("/proc/self/status").unwrap();
+
+    status.lines()
+        .find(|line| line.starts_with("Threads:"))
+        .and_then(|line| line["Threads:".len()..].trim().parse().ok())
+        .unwrap()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn workers_are_joined_after_panic() {
+    let file = env::temp_dir().join(format!("syncbox-fuzz-thread-shrink-{}.json", process::id()));
+    let before = os_threads();
+
+    for _ in 0..10 {
+        let mut builder = builder(Runtime::Thread);
+        builder.shrink_file(file.to_str().unwrap());
+
+        // Shrinking replays the failure, each time with the spawned thread
+        // waiting for its turn when the root thread panics.
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            builder.fuzz(|| {
+                let num = Arc::new(AtomicUsize::new(0));
+
+                let th = {
+                    let num = num.clone();
+                    thread::spawn(move || {
+                        num.store(1, SeqCst);
+                        num.store(2, SeqCst);
+                    })
+                };
+
+                assert_ne!(1, num.load(SeqCst));
+                th.join().unwrap();
+            });
+        }));
+
+        assert!(res.is_err());
+    }
+
+    let _ = fs::remove_file(&file);
+
+    // Other tests may be running their own workers
+    assert!(os_threads() < before + 5, "before = {}; after = {}", before, os_threads());
+}
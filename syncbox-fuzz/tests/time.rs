@@ -0,0 +1,73 @@
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::sync::{Condvar, Mutex};
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+use syncbox_fuzz::time::{self, Duration, Instant};
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+#[test]
+fn sleep_advances_clock() {
+    syncbox_fuzz::fuzz(|| {
+        let start = Instant::now();
+
+        time::sleep(Duration::from_millis(10));
+
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    });
+}
+
+#[test]
+fn condvar_wait_timeout_without_notify() {
+    syncbox_fuzz::fuzz(|| {
+        let mutex = Mutex::new(());
+        let condvar = Condvar::new();
+
+        let guard = mutex.lock().unwrap();
+        let (_guard, res) = condvar.wait_timeout(guard, Duration::from_millis(10)).unwrap();
+
+        assert!(res.timed_out());
+    });
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: !res.timed_out()")]
+fn condvar_wait_timeout_races_notify() {
+    struct Inc {
+        num: AtomicUsize,
+        mutex: Mutex<()>,
+        condvar: Condvar,
+    }
+
+    syncbox_fuzz::fuzz(|| {
+        let inc = Arc::new(Inc {
+            num: AtomicUsize::new(0),
+            mutex: Mutex::new(()),
+            condvar: Condvar::new(),
+        });
+
+        {
+            let inc = inc.clone();
+
+            thread::spawn(move || {
+                inc.num.store(1, SeqCst);
+                drop(inc.mutex.lock().unwrap());
+                inc.condvar.notify_one();
+            });
+        }
+
+        let mut guard = inc.mutex.lock().unwrap();
+
+        while 0 == inc.num.load(SeqCst) {
+            let (g, res) = inc.condvar
+                .wait_timeout(guard, Duration::from_millis(10))
+                .unwrap();
+
+            // The notification may lose the race with the timeout
+            assert!(!res.timed_out());
+            guard = g;
+        }
+    });
+}
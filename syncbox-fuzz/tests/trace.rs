@@ -0,0 +1,76 @@
+extern crate serde_json;
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::Mutex;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use serde_json::Value;
+
+use std::env;
+use std::fs::{self, File};
+use std::panic;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::Ordering::SeqCst;
+
+#[test]
+fn timeline_of_failing_execution() {
+    let file = env::temp_dir().join(format!("syncbox-fuzz-trace-{}.json", process::id()));
+
+    let mut builder = Builder::new();
+    builder.trace_file(file.to_str().unwrap());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.fuzz(|| {
+            let num = Arc::new(AtomicUsize::new(0));
+            let lock = Arc::new(Mutex::new(()));
+
+            let th = {
+                let (num, lock) = (num.clone(), lock.clone());
+
+                thread::spawn(move || {
+                    let _guard = lock.lock().unwrap();
+                    num.fetch_add(1, SeqCst);
+                })
+            };
+
+            th.join().unwrap();
+            assert_eq!(0, num.load(SeqCst));
+        });
+    }));
+
+    assert!(res.is_err());
+
+    let trace: Value = serde_json::from_reader(File::open(&file).unwrap()).unwrap();
+    fs::remove_file(&file).unwrap();
+
+    let events = trace["traceEvents"].as_array().unwrap();
+
+    let find = |ph: &str, name: &str| {
+        events.iter()
+            .filter(|event| event["ph"] == ph && event["name"].as_str().unwrap().contains(name))
+            .collect::<Vec<_>>()
+    };
+
+    // One named track per thread
+    let tracks = find("M", "thread_name");
+    assert_eq!(2, tracks.len());
+
+    // The spawned thread holds the mutex around its increment
+    let begin = find("B", "held");
+    let end = find("E", "held");
+    assert_eq!(1, begin.len());
+    assert_eq!(1, end.len());
+    assert_eq!(1, begin[0]["tid"]);
+
+    let rmw = find("i", ": rmw");
+    assert_eq!(1, rmw.len());
+    assert_eq!(1, rmw[0]["tid"]);
+    assert!(begin[0]["ts"].as_u64() <= rmw[0]["ts"].as_u64());
+    assert!(rmw[0]["ts"].as_u64() <= end[0]["ts"].as_u64());
+
+    assert!(!find("i", ": load").is_empty());
+    assert!(!find("i", "park").is_empty());
+}
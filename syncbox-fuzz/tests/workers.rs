@@ -0,0 +1,187 @@
+extern crate serde_json;
+extern crate syncbox_fuzz;
+
+use syncbox_fuzz::fuzz::Builder;
+use syncbox_fuzz::sync::Mutex;
+use syncbox_fuzz::sync::atomic::AtomicUsize;
+use syncbox_fuzz::thread;
+
+use serde_json::Value;
+
+use std::env;
+use std::fs::{self, File};
+use std::panic;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize as StdAtomicUsize;
+use std::sync::atomic::Ordering::{Acquire, Release, Relaxed, SeqCst};
+
+#[test]
+fn workers_valid() {
+    let mut fuzz = syncbox_fuzz::fuzz::Builder::new();
+    fuzz.workers(4);
+
+    fuzz.fuzz(|| {
+        let data = Arc::new((Mutex::new(0), AtomicUsize::new(0)));
+
+        let ths: Vec<_> = (0..2).map(|_| {
+            let data = data.clone();
+
+            thread::spawn(move || {
+                let mut locked = data.0.lock().unwrap();
+
+                let prev = data.1.fetch_add(1, SeqCst);
+                assert_eq!(prev, *locked);
+                *locked += 1;
+            })
+        }).collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        let locked = data.0.lock().unwrap();
+
+        assert_eq!(*locked, data.1.load(SeqCst));
+    });
+}
+
+#[test]
+#[should_panic(expected = "lost update")]
+fn workers_check_fail() {
+    let mut fuzz = syncbox_fuzz::fuzz::Builder::new();
+    fuzz.workers(4);
+
+    fuzz.fuzz(lost_update);
+}
+
+fn lost_update() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..2).map(|_| {
+        let num = num.clone();
+
+        thread::spawn(move || {
+            let curr = num.load(Acquire);
+            num.store(curr + 1, Release);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(2, num.load(Relaxed), "lost update");
+}
+
+fn spawn_and_add() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let ths: Vec<_> = (0..3).map(|_| {
+        let num = num.clone();
+        thread::spawn(move || {
+            num.fetch_add(1, Relaxed);
+            num.fetch_add(1, Relaxed);
+        })
+    }).collect();
+
+    for th in ths {
+        th.join().unwrap();
+    }
+
+    assert_eq!(6, num.load(Relaxed));
+}
+
+fn checkpoint_file(name: &str) -> PathBuf {
+    let file = env::temp_dir().join(format!("syncbox-fuzz-workers-{}-{}.json", name, process::id()));
+    let _ = fs::remove_file(&file);
+    file
+}
+
+#[test]
+fn checkpoint_every_interval() {
+    let file = checkpoint_file("interval");
+
+    let full = Builder::new().fuzz(spawn_and_add);
+
+    let mut builder = Builder::new();
+    builder.workers(4);
+    builder.checkpoint_file = Some(file.clone());
+    builder.checkpoint_interval = 10;
+
+    let report = builder.fuzz(spawn_and_add);
+    assert!(report.complete);
+
+    // The last checkpoint holds the paths of the busy workers and the queue
+    let checkpoint: Value = serde_json::from_reader(File::open(&file).unwrap()).unwrap();
+    assert!(checkpoint.as_array().is_some());
+
+    let rest = builder.fuzz(spawn_and_add);
+    fs::remove_file(&file).unwrap();
+
+    assert!(rest.complete);
+    assert!(rest.executions > 0);
+    assert!(rest.executions < full.executions);
+}
+
+#[test]
+fn checkpoint_on_failure() {
+    let file = checkpoint_file("failure");
+
+    let mut builder = Builder::new();
+    builder.workers(4);
+    builder.checkpoint_file = Some(file.clone());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.fuzz(lost_update);
+    }));
+
+    assert!(res.is_err());
+
+    // Resuming starts with the failing execution
+    let runs = Arc::new(StdAtomicUsize::new(0));
+
+    builder.workers(1);
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let runs = runs.clone();
+
+        builder.fuzz(move || {
+            runs.fetch_add(1, SeqCst);
+            lost_update();
+        });
+    }));
+
+    fs::remove_file(&file).unwrap();
+
+    assert!(res.is_err());
+    assert_eq!(1, runs.load(SeqCst));
+}
+
+#[test]
+fn split_checkpoint_without_kinds() {
+    let file = checkpoint_file("kinds");
+
+    let full = Builder::new().fuzz(spawn_and_add);
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file = Some(file.clone());
+    builder.max_iterations(10);
+
+    let first = builder.fuzz(spawn_and_add);
+
+    // Checkpoints written before branch kinds were recorded
+    let mut checkpoint: Value = serde_json::from_reader(File::open(&file).unwrap()).unwrap();
+    checkpoint.as_object_mut().unwrap().remove("kinds").unwrap();
+    serde_json::to_writer(File::create(&file).unwrap(), &checkpoint).unwrap();
+
+    builder.max_iterations = None;
+    builder.workers(4);
+
+    let rest = builder.fuzz(spawn_and_add);
+    fs::remove_file(&file).unwrap();
+
+    assert!(rest.complete);
+    assert_eq!(full.executions, first.executions + rest.executions);
+}